@@ -0,0 +1,39 @@
+use std::time::{Duration, Instant};
+use crate::{fs, SharedState};
+
+/// How often to check whether an autosave is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Saves the loaded config once it has had unsaved changes for the project's
+/// autosave interval. Runs for as long as the app does.
+pub async fn run_autosave(shared_state: SharedState) {
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+    let mut last_saved = Instant::now();
+
+    loop {
+        ticker.tick().await;
+        let mut state = shared_state.lock().unwrap();
+        let interval = state.data.settings.autosave_interval;
+        let Some(path) = state.loaded_config.clone() else {
+            continue;
+        };
+        // Don't overwrite outside edits before the operator decides what to do with them
+        if state.external_change.is_some() {
+            continue;
+        }
+        let Some(interval) = interval.filter(|_| state.is_dirty()) else {
+            // Start counting from the first unsaved change
+            last_saved = Instant::now();
+            continue;
+        };
+        if last_saved.elapsed() < Duration::from_secs(interval) {
+            continue;
+        }
+
+        last_saved = Instant::now();
+        match fs::autosave_config_file(&mut state, &path) {
+            Ok(()) => state.mark_saved(),
+            Err(err) => eprintln!("Error autosaving config file: {}", err),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use chrono::{Local, NaiveDateTime};
+use serde::Serialize;
+use std::{fs, path::{Path, PathBuf}};
+
+use crate::{error::{CommandError, CommandResult}, fs::write_atomically};
+
+/// How many previous versions of each config file are kept
+pub const MAX_BACKUPS: usize = 10;
+
+const BACKUP_DIR: &str = ".backups";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S-%3f";
+
+/// A previous version of a config file, stored in the `.backups` folder next
+/// to it as `<config name>.<timestamp>.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Backup {
+    pub name: String,
+    /// When the backup was made, e.g. `2024-10-18 14:03:22`
+    pub created: String,
+    pub size: u64,
+}
+
+pub fn backup_dir(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(BACKUP_DIR)
+}
+
+fn config_stem(config_path: &Path) -> String {
+    config_path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Reads the timestamp back out of a backup's name, as long as it belongs
+/// to the config with the given stem.
+fn backup_timestamp(stem: &str, name: &str) -> Option<NaiveDateTime> {
+    let timestamp = name.strip_prefix(stem)?
+        .strip_prefix('.')?
+        .strip_suffix(".json")?;
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()
+}
+
+/// Lists the backups of a config file, newest first.
+pub fn list_backups(config_path: &Path) -> CommandResult<Vec<Backup>> {
+    let dir = backup_dir(config_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let stem = config_stem(config_path);
+    let mut backups: Vec<(NaiveDateTime, Backup)> = fs::read_dir(&dir)
+        .map_err(|err| CommandError::io(&dir, err))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let timestamp = backup_timestamp(&stem, &name)?;
+            let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            let created = timestamp.format("%Y-%m-%d %H:%M:%S").to_string();
+            Some((timestamp, Backup { name, created, size }))
+        })
+        .collect();
+    backups.sort_by(|(a, _), (b, _)| b.cmp(a));
+    Ok(backups.into_iter().map(|(_, backup)| backup).collect())
+}
+
+/// Copies the config file as it is on disk into the backup folder and
+/// removes the oldest backups past `MAX_BACKUPS`. Does nothing if the file
+/// doesn't exist yet.
+pub fn back_up(config_path: &Path) -> CommandResult<Option<PathBuf>> {
+    if !config_path.exists() {
+        return Ok(None);
+    }
+
+    let dir = backup_dir(config_path);
+    fs::create_dir_all(&dir)
+        .map_err(|err| CommandError::io(&dir, err))?;
+
+    let contents = fs::read(config_path)
+        .map_err(|err| CommandError::io(config_path, err))?;
+    let timestamp = Local::now().format(TIMESTAMP_FORMAT);
+    let backup_path = dir.join(format!("{}.{}.json", config_stem(config_path), timestamp));
+    write_atomically(&backup_path, &contents)
+        .map_err(|err| CommandError::io(&backup_path, err))?;
+
+    for old_backup in list_backups(config_path)?.into_iter().skip(MAX_BACKUPS) {
+        let old_path = dir.join(&old_backup.name);
+        if let Err(err) = fs::remove_file(&old_path) {
+            eprintln!("Error removing old backup {:?}: {:?}", old_path, err);
+        }
+    }
+    Ok(Some(backup_path))
+}
+
+/// Replaces the config file with one of its backups. The current file is
+/// backed up first, so restoring can itself be undone.
+pub fn restore_backup(config_path: &Path, name: &str) -> CommandResult<()> {
+    // Only accept names that were actually listed, which also keeps the
+    // name from pointing outside the backup folder
+    let backup = list_backups(config_path)?
+        .into_iter()
+        .find(|backup| backup.name == name)
+        .ok_or_else(|| CommandError::validation(format!("No backup named {:?}", name)))?;
+
+    let backup_path = backup_dir(config_path).join(&backup.name);
+    let contents = fs::read(&backup_path)
+        .map_err(|err| CommandError::io(&backup_path, err))?;
+    back_up(config_path)?;
+    write_atomically(config_path, &contents)
+        .map_err(|err| CommandError::io(config_path, err))?;
+    println!("Restored config file {:?} from {:?}", config_path, backup_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::new_id;
+
+    /// A folder of its own for each test, removed again afterwards.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("scoreboard-backups-{}", new_id()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn backup_contents(config_path: &Path, backup: &Backup) -> String {
+        fs::read_to_string(backup_dir(config_path).join(&backup.name)).unwrap()
+    }
+
+    #[test]
+    fn timestamps_are_read_from_backup_names() {
+        let timestamp = backup_timestamp("event", "event.2024-10-18_14-03-22-123.json").unwrap();
+        assert_eq!(timestamp.format("%Y-%m-%d %H:%M:%S%.3f").to_string(), "2024-10-18 14:03:22.123");
+        assert_eq!(backup_timestamp("event", "event2.2024-10-18_14-03-22-123.json"), None);
+        assert_eq!(backup_timestamp("event", "other.2024-10-18_14-03-22-123.json"), None);
+        assert_eq!(backup_timestamp("event", "event.2024-10-18_14-03-22-123.txt"), None);
+        assert_eq!(backup_timestamp("event", "event.yesterday.json"), None);
+    }
+
+    #[test]
+    fn only_the_newest_backups_are_kept() {
+        let dir = TempDir::new();
+        let config_path = dir.0.join("event.json");
+        fs::write(&config_path, "current").unwrap();
+        let backups = backup_dir(&config_path);
+        fs::create_dir_all(&backups).unwrap();
+        for second in 0..MAX_BACKUPS + 2 {
+            let name = format!("event.2020-01-01_00-00-{:02}-000.json", second);
+            fs::write(backups.join(name), second.to_string()).unwrap();
+        }
+        let unrelated = backups.join("other.2020-01-01_00-00-00-000.json");
+        fs::write(&unrelated, "other").unwrap();
+
+        back_up(&config_path).unwrap().unwrap();
+        let listed = list_backups(&config_path).unwrap();
+        assert_eq!(listed.len(), MAX_BACKUPS);
+        assert_eq!(backup_contents(&config_path, &listed[0]), "current");
+        // The three oldest went to make room for the new one
+        assert_eq!(backup_contents(&config_path, &listed[MAX_BACKUPS - 1]), "3");
+        assert!(unrelated.exists());
+    }
+
+    #[test]
+    fn restoring_backs_up_the_replaced_file_first() {
+        let dir = TempDir::new();
+        let config_path = dir.0.join("event.json");
+        assert_eq!(back_up(&config_path).unwrap(), None);
+        fs::write(&config_path, "first").unwrap();
+        back_up(&config_path).unwrap().unwrap();
+        let first = list_backups(&config_path).unwrap().remove(0);
+        fs::write(&config_path, "second").unwrap();
+
+        // Backups are named to the millisecond
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        restore_backup(&config_path, &first.name).unwrap();
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), "first");
+        let listed = list_backups(&config_path).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(backup_contents(&config_path, &listed[0]), "second");
+
+        assert!(restore_backup(&config_path, "../event.json").is_err());
+    }
+}
@@ -0,0 +1,861 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::{HashMap, HashSet}, fmt};
+
+use crate::{
+    error::{CommandError, CommandResult},
+    models::{BracketFormat, Division, Id, Matchup, SaveData},
+};
+
+/// Which part of the bracket a matchup belongs to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BracketSide {
+    Upper,
+    Lower,
+    GrandFinal,
+    /// Only played if the lower bracket's team wins the grand final
+    GrandFinalReset,
+}
+
+/// Where a matchup sits in the bracket. Stages and indices start from 0, and
+/// are always 0 for the grand final and its reset.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct BracketPosition {
+    pub side: BracketSide,
+    pub stage: usize,
+    pub index: usize,
+}
+
+impl BracketPosition {
+    pub fn new(side: BracketSide, stage: usize, index: usize) -> Self {
+        Self {
+            side,
+            stage,
+            index,
+        }
+    }
+
+    pub fn upper(stage: usize, index: usize) -> Self {
+        Self::new(BracketSide::Upper, stage, index)
+    }
+
+    pub fn lower(stage: usize, index: usize) -> Self {
+        Self::new(BracketSide::Lower, stage, index)
+    }
+
+    pub fn grand_final() -> Self {
+        Self::new(BracketSide::GrandFinal, 0, 0)
+    }
+
+    pub fn grand_final_reset() -> Self {
+        Self::new(BracketSide::GrandFinalReset, 0, 0)
+    }
+}
+
+impl fmt::Display for BracketPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.side {
+            BracketSide::Upper =>
+                write!(f, "bracket stage {}, matchup {}", self.stage + 1, self.index + 1),
+            BracketSide::Lower =>
+                write!(f, "lower bracket stage {}, matchup {}", self.stage + 1, self.index + 1),
+            BracketSide::GrandFinal =>
+                write!(f, "grand final"),
+            BracketSide::GrandFinalReset =>
+                write!(f, "grand final reset"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Winner,
+    Loser,
+}
+
+/// One side of a matchup, `team` being 1 or 2.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct BracketSlot {
+    pub position: BracketPosition,
+    pub team: usize,
+}
+
+impl BracketSlot {
+    pub fn new(position: BracketPosition, team: usize) -> Self {
+        Self {
+            position,
+            team,
+        }
+    }
+}
+
+/// Where the team in a slot comes from, e.g. the loser of upper bracket stage 2, matchup 1.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Feed {
+    pub from: BracketPosition,
+    pub outcome: Outcome,
+}
+
+/// The most stages a bracket can have, enough for 256 teams. Every stage
+/// doubles the size of the first, so much more than this would never fit in memory.
+pub const MAX_STAGE_COUNT: usize = 8;
+
+pub fn check_stage_count(stage_count: usize) -> Result<(), String> {
+    if stage_count > MAX_STAGE_COUNT {
+        return Err(format!("A bracket can have at most {} stages, not {}", MAX_STAGE_COUNT, stage_count));
+    }
+    Ok(())
+}
+
+/// Each stage has half as many matchups as the one before it, ending with the final.
+pub fn upper_bracket_sizes(stage_count: usize) -> Vec<usize> {
+    (0..stage_count)
+        .map(|stage| 1 << (stage_count - 1 - stage))
+        .collect()
+}
+
+/// The lower bracket alternates between stages where its own winners play
+/// each other and stages where they meet the teams dropping down from the
+/// upper bracket, so it has two stages for every upper stage after the
+/// first. With 8 teams (3 upper stages) that's 2, 2, 1 and 1 matchups.
+pub fn lower_bracket_sizes(upper_stage_count: usize) -> Vec<usize> {
+    (0..upper_stage_count.saturating_sub(1) * 2)
+        .map(|stage| 1 << (upper_stage_count - 2 - stage / 2))
+        .collect()
+}
+
+/// Where the winner of a matchup plays next, if anywhere. The winners of the
+/// grand final and the upper final of a single elimination bracket have won
+/// the whole thing, and the grand final reset is handled separately since
+/// it's only played sometimes.
+pub fn winner_destination(format: &BracketFormat, upper_stage_count: usize, from: BracketPosition) -> Option<BracketSlot> {
+    let double_elimination = format.is_double_elimination();
+    let lower_stage_count = lower_bracket_sizes(upper_stage_count).len();
+
+    match from.side {
+        BracketSide::Upper if from.stage + 1 < upper_stage_count =>
+            Some(BracketSlot::new(BracketPosition::upper(from.stage + 1, from.index / 2), from.index % 2 + 1)),
+        BracketSide::Upper if double_elimination =>
+            Some(BracketSlot::new(BracketPosition::grand_final(), 1)),
+        BracketSide::Lower if !double_elimination =>
+            None,
+        BracketSide::Lower if from.stage + 1 < lower_stage_count && from.stage % 2 == 1 =>
+            Some(BracketSlot::new(BracketPosition::lower(from.stage + 1, from.index / 2), from.index % 2 + 1)),
+        // The first of each pair of lower stages feeds a stage the same size,
+        // where the teams from the upper bracket take the other side
+        BracketSide::Lower if from.stage + 1 < lower_stage_count =>
+            Some(BracketSlot::new(BracketPosition::lower(from.stage + 1, from.index), 1)),
+        BracketSide::Lower =>
+            Some(BracketSlot::new(BracketPosition::grand_final(), 2)),
+        _ => None,
+    }
+}
+
+/// Where the loser of a matchup drops down to in a double elimination
+/// bracket. Every other stage's losers are dropped in reverse order, so teams
+/// that met in the upper bracket don't meet again straight away.
+pub fn loser_destination(format: &BracketFormat, upper_stage_count: usize, from: BracketPosition) -> Option<BracketSlot> {
+    if !format.is_double_elimination() || from.side != BracketSide::Upper || from.stage >= upper_stage_count {
+        return None;
+    }
+
+    let lower_sizes = lower_bracket_sizes(upper_stage_count);
+    if lower_sizes.is_empty() {
+        // With only two teams, there's nobody else to play before the grand final
+        return Some(BracketSlot::new(BracketPosition::grand_final(), 2));
+    }
+    if from.stage == 0 {
+        return Some(BracketSlot::new(BracketPosition::lower(0, from.index / 2), from.index % 2 + 1));
+    }
+
+    let stage = from.stage * 2 - 1;
+    let index = if from.stage % 2 == 1 {
+        lower_sizes[stage].saturating_sub(from.index + 1)
+    } else {
+        from.index
+    };
+    Some(BracketSlot::new(BracketPosition::lower(stage, index), 2))
+}
+
+/// Every position the bracket is made of, whether or not a matchup has been
+/// set up there yet.
+pub fn positions(format: &BracketFormat, division: &Division) -> Vec<BracketPosition> {
+    let upper = division.bracket.iter().enumerate()
+        .flat_map(|(stage, matchups)| (0..matchups.len()).map(move |index| BracketPosition::upper(stage, index)));
+    let mut positions: Vec<BracketPosition> = upper.collect();
+
+    if let BracketFormat::DoubleElimination { bracket_reset } = format {
+        let lower = division.lower_bracket.iter().enumerate()
+            .flat_map(|(stage, matchups)| (0..matchups.len()).map(move |index| BracketPosition::lower(stage, index)));
+        positions.extend(lower);
+        positions.push(BracketPosition::grand_final());
+        if *bracket_reset {
+            positions.push(BracketPosition::grand_final_reset());
+        }
+    }
+
+    positions
+}
+
+/// Works out where the team in every slot of the bracket comes from, for
+/// templates that want to label or draw lines between them.
+pub fn feeds(format: &BracketFormat, division: &Division) -> HashMap<BracketSlot, Feed> {
+    let upper_stage_count = division.bracket.len();
+    let mut feeds = HashMap::new();
+    for from in positions(format, division) {
+        if let Some(slot) = winner_destination(format, upper_stage_count, from) {
+            feeds.insert(slot, Feed { from, outcome: Outcome::Winner });
+        }
+        if let Some(slot) = loser_destination(format, upper_stage_count, from) {
+            feeds.insert(slot, Feed { from, outcome: Outcome::Loser });
+        }
+    }
+    feeds
+}
+
+/// Puts `team` into a slot, setting up the matchup there if needed. A
+/// completed matchup stops being completed if one of its teams changes, since
+/// its result was against someone else. Returns whether that happened.
+fn place_team(division: &mut Division, slot: BracketSlot, team: Option<Id>) -> bool {
+    let Some(entry) = division.bracket_entry_mut(slot.position) else {
+        return false;
+    };
+    if entry.is_none() && team.is_none() {
+        return false;
+    }
+    let matchup = entry.get_or_insert_with(Matchup::default);
+    let current = if slot.team == 1 { &mut matchup.team1 } else { &mut matchup.team2 };
+    if *current == team {
+        return false;
+    }
+    *current = team;
+    std::mem::replace(&mut matchup.completed, false)
+}
+
+/// Who a matchup sends on, and whether it's a bye, which is all that
+/// matters to the matchups it feeds.
+fn outcome(matchup: Option<&Matchup>) -> (Option<Id>, Option<Id>, bool) {
+    matchup.map_or((None, None, false), |matchup| (
+        matchup.winner().cloned(),
+        matchup.loser().cloned(),
+        matchup.completed && !matchup.is_filled(),
+    ))
+}
+
+/// Sends the teams of every matchup that `changed` picks out on to where
+/// they play next, along with those of every matchup whose result is undone
+/// along the way. Positions are visited in the order teams move through
+/// them, so a correction early on carries all the way through.
+///
+/// Byes have no loser, so the slots their losers would drop into stay empty
+/// for good, and the matchups there become byes too.
+fn propagate(
+    format: &BracketFormat,
+    division: &mut Division,
+    changed: impl Fn(BracketPosition, Option<&Matchup>) -> bool,
+) {
+    let upper_stage_count = division.bracket.len();
+    let mut empty_slots = HashSet::new();
+    let mut undone = HashSet::new();
+    for from in positions(format, division) {
+        let mut is_changed = undone.contains(&from) || changed(from, division.bracket_matchup(from));
+        let empty_count = [1, 2].iter()
+            .filter(|&&team| empty_slots.contains(&BracketSlot::new(from, team)))
+            .count();
+        if empty_count > 0 {
+            if let Some(entry) = division.bracket_entry_mut(from) {
+                // Nobody is coming for the empty side, so whoever is on the other goes straight through
+                let matchup = entry.get_or_insert_with(Matchup::default);
+                let completed = empty_count == 2 || matchup.team1.is_some() || matchup.team2.is_some();
+                is_changed |= matchup.completed != completed;
+                matchup.completed = completed;
+            }
+        }
+
+        let Some(matchup) = division.bracket_matchup(from) else {
+            continue;
+        };
+        let winner_slot = winner_destination(format, upper_stage_count, from);
+        let loser_slot = loser_destination(format, upper_stage_count, from);
+        // Even byes that haven't changed leave slots empty further on
+        if matchup.completed && !matchup.is_filled() {
+            let is_empty = matchup.team1.is_none() && matchup.team2.is_none();
+            empty_slots.extend(winner_slot.filter(|_| is_empty));
+            empty_slots.extend(loser_slot);
+        }
+        if !is_changed {
+            continue;
+        }
+
+        let winner = matchup.winner().cloned();
+        let loser = matchup.loser().cloned();
+        for (slot, team) in [(winner_slot, winner), (loser_slot, loser)] {
+            if let Some(slot) = slot {
+                if place_team(division, slot, team) {
+                    undone.insert(slot.position);
+                }
+            }
+        }
+    }
+
+    // The same two teams play again, but only if the lower bracket's team won
+    let grand_final = BracketPosition::grand_final();
+    let grand_final_changed = undone.contains(&grand_final) || changed(grand_final, division.grand_final.as_ref());
+    if let (BracketFormat::DoubleElimination { bracket_reset: true }, true) = (format, grand_final_changed) {
+        let (team1, team2) = division.grand_final.as_ref()
+            .filter(|grand_final| grand_final.winner().is_some() && grand_final.winner() == grand_final.team2.as_ref())
+            .map(|grand_final| (grand_final.team1.clone(), grand_final.team2.clone()))
+            .unwrap_or_default();
+        place_team(division, BracketSlot::new(BracketPosition::grand_final_reset(), 1), team1);
+        place_team(division, BracketSlot::new(BracketPosition::grand_final_reset(), 2), team2);
+    }
+}
+
+/// Fills every slot that another matchup feeds with the team that matchup
+/// sends there, or empties it if that matchup hasn't been decided yet. Slots
+/// fed by a matchup that hasn't been set up are left for the operator to fill.
+/// This rebuilds the whole bracket from its results, so teams put into later
+/// stages by hand are taken back out unless the matchups before them are completed.
+pub fn advance_teams(format: &BracketFormat, division: &mut Division) {
+    propagate(format, division, |_, _| true);
+}
+
+/// Moves teams on from only the matchups whose result is different from
+/// `before`, and from whatever that changes further on. Brackets filled in by
+/// hand usually don't have their matchups marked as completed, so everything
+/// else is left as it is.
+pub fn advance_changed_teams(format: &BracketFormat, before: &Division, division: &mut Division) {
+    propagate(format, division, |position, matchup| outcome(before.bracket_matchup(position)) != outcome(matchup));
+}
+
+/// Moves teams on from the completed matchups that send them somewhere else
+/// now that the bracket has `division.bracket.len()` stages instead of
+/// `old_stage_count`, such as the old final in a bigger bracket.
+fn advance_resized_teams(format: &BracketFormat, old_stage_count: usize, division: &mut Division) {
+    let stage_count = division.bracket.len();
+    propagate(format, division, |position, matchup| {
+        let moved = winner_destination(format, old_stage_count, position) != winner_destination(format, stage_count, position)
+            || loser_destination(format, old_stage_count, position) != loser_destination(format, stage_count, position);
+        moved && matchup.is_some_and(|matchup| matchup.completed)
+    });
+}
+
+/// What resizing the bracket to `stage_count` stages would do, so it can be
+/// checked before it's applied.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ResizePreview {
+    pub stage_count: usize,
+    /// Matchups with a team in them that wouldn't fit anymore
+    pub discarded: Vec<BracketPosition>,
+}
+
+/// The order seeds are placed down the first stage of a bracket with
+/// `2^stage_count` slots, counting from 0, so the best seeds can only meet in
+/// the later stages. With 8 slots, it's 1 v 8, 4 v 5, 2 v 7 and 3 v 6.
+pub fn seed_order(stage_count: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    for _ in 0..stage_count {
+        let slot_count = order.len() * 2;
+        order = order.iter()
+            .flat_map(|&seed| [seed, slot_count - 1 - seed])
+            .collect();
+    }
+    order
+}
+
+impl SaveData {
+    pub fn preview_bracket_resize(&self, stage_count: usize) -> CommandResult<ResizePreview> {
+        check_stage_count(stage_count).map_err(CommandError::validation)?;
+        let upper_sizes = upper_bracket_sizes(stage_count);
+        let lower_sizes = lower_bracket_sizes(stage_count);
+        let fits = |position: &BracketPosition| {
+            let sizes = match position.side {
+                BracketSide::Upper => &upper_sizes,
+                BracketSide::Lower => &lower_sizes,
+                BracketSide::GrandFinal | BracketSide::GrandFinalReset => return true,
+            };
+            sizes.get(position.stage).is_some_and(|&size| position.index < size)
+        };
+
+        let discarded = positions(&self.settings.bracket_format, &self.division).into_iter()
+            .filter(|position| !fits(position))
+            .filter(|&position| self.division.bracket_matchup(position)
+                .is_some_and(|matchup| matchup.team1.is_some() || matchup.team2.is_some()))
+            .collect();
+        Ok(ResizePreview {
+            stage_count,
+            discarded,
+        })
+    }
+
+    /// Changes the number of stages in the bracket, keeping everything that
+    /// still fits (see `preview_bracket_resize` for what doesn't).
+    pub fn resize_bracket(&mut self, stage_count: usize) -> CommandResult<()> {
+        check_stage_count(stage_count).map_err(CommandError::validation)?;
+        let old_stage_count = self.division.bracket.len();
+        self.settings.bracket_stage_count = stage_count;
+        self.correct_bracket_to_count();
+        advance_resized_teams(&self.settings.bracket_format, old_stage_count, &mut self.division);
+        Ok(())
+    }
+
+    /// Replaces the bracket with one just big enough for `seeds` (best
+    /// first), with the first stage paired up by seed. When there aren't
+    /// enough teams to fill it, the best seeds get byes straight to the second stage.
+    pub fn seed_bracket(&mut self, seeds: &[Id]) -> CommandResult<()> {
+        if seeds.len() < 2 {
+            return Err(CommandError::validation("A bracket needs at least two teams"));
+        }
+        let mut seen = HashSet::new();
+        for seed in seeds {
+            if self.division.team(seed).is_none() {
+                return Err(CommandError::validation(format!("No team with ID {}", seed)));
+            }
+            if !seen.insert(seed) {
+                return Err(CommandError::validation(format!("{} is seeded more than once", self.division.team(seed).unwrap().name)));
+            }
+        }
+
+        let stage_count = (usize::BITS - (seeds.len() - 1).leading_zeros()) as usize;
+        check_stage_count(stage_count).map_err(CommandError::validation)?;
+        self.settings.bracket_stage_count = stage_count;
+        self.division.bracket.clear();
+        self.division.lower_bracket.clear();
+        self.division.grand_final = None;
+        self.division.grand_final_reset = None;
+        self.correct_bracket_to_count();
+
+        let order = seed_order(stage_count);
+        for (index, pair) in order.chunks(2).enumerate() {
+            let team1 = seeds.get(pair[0]).cloned();
+            let team2 = seeds.get(pair[1]).cloned();
+            let is_bye = team2.is_none();
+            self.division.bracket[0][index] = Some(Matchup::new(team1, team2, 0, 0, is_bye));
+        }
+        self.advance_bracket();
+        Ok(())
+    }
+}
+
+impl Division {
+    pub fn bracket_matchup(&self, position: BracketPosition) -> Option<&Matchup> {
+        match position.side {
+            BracketSide::Upper => self.bracket.get(position.stage)?.get(position.index)?.as_ref(),
+            BracketSide::Lower => self.lower_bracket.get(position.stage)?.get(position.index)?.as_ref(),
+            BracketSide::GrandFinal => self.grand_final.as_ref(),
+            BracketSide::GrandFinalReset => self.grand_final_reset.as_ref(),
+        }
+    }
+
+    /// The place a matchup at `position` is kept, which is empty until one is set up there.
+    pub fn bracket_entry_mut(&mut self, position: BracketPosition) -> Option<&mut Option<Matchup>> {
+        match position.side {
+            BracketSide::Upper => self.bracket.get_mut(position.stage)?.get_mut(position.index),
+            BracketSide::Lower => self.lower_bracket.get_mut(position.stage)?.get_mut(position.index),
+            BracketSide::GrandFinal => Some(&mut self.grand_final),
+            BracketSide::GrandFinalReset => Some(&mut self.grand_final_reset),
+        }
+    }
+
+    /// Every matchup in the bracket along with where it is, including any in
+    /// parts the current format doesn't use.
+    pub fn bracket_matchups_mut(&mut self) -> impl Iterator<Item = (BracketPosition, &mut Matchup)> {
+        let upper = self.bracket.iter_mut().enumerate()
+            .flat_map(|(stage, matchups)| matchups.iter_mut().enumerate()
+                .filter_map(move |(index, matchup)| Some((BracketPosition::upper(stage, index), matchup.as_mut()?))));
+        let lower = self.lower_bracket.iter_mut().enumerate()
+            .flat_map(|(stage, matchups)| matchups.iter_mut().enumerate()
+                .filter_map(move |(index, matchup)| Some((BracketPosition::lower(stage, index), matchup.as_mut()?))));
+        let grand_final = self.grand_final.as_mut()
+            .map(|matchup| (BracketPosition::grand_final(), matchup));
+        let grand_final_reset = self.grand_final_reset.as_mut()
+            .map(|matchup| (BracketPosition::grand_final_reset(), matchup));
+        upper.chain(lower).chain(grand_final).chain(grand_final_reset)
+    }
+
+    pub fn bracket_matchups(&self) -> impl Iterator<Item = &Matchup> {
+        self.bracket.iter().flatten()
+            .chain(self.lower_bracket.iter().flatten())
+            .flatten()
+            .chain(self.grand_final.as_ref())
+            .chain(self.grand_final_reset.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Settings, Team};
+
+    fn team(name: &str) -> Option<Id> {
+        Some(name.to_string())
+    }
+
+    fn played(team1: &str, team2: &str, team1_score: usize, team2_score: usize) -> Option<Matchup> {
+        Some(Matchup::new(team(team1), team(team2), team1_score, team2_score, true))
+    }
+
+    fn empty_bracket(stage_count: usize) -> Division {
+        Division {
+            bracket: upper_bracket_sizes(stage_count).into_iter().map(|size| vec![None; size]).collect(),
+            lower_bracket: lower_bracket_sizes(stage_count).into_iter().map(|size| vec![None; size]).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn teams_at(division: &Division, position: BracketPosition) -> (Option<Id>, Option<Id>) {
+        division.bracket_matchup(position)
+            .map(|matchup| (matchup.team1.clone(), matchup.team2.clone()))
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn changed_results_keep_teams_placed_by_hand() {
+        let format = BracketFormat::SingleElimination;
+        let mut division = empty_bracket(3);
+        // Filled in by hand, without marking anything completed
+        division.bracket[0][0] = Some(Matchup::new(team("a"), team("b"), 0, 0, false));
+        division.bracket[0][1] = Some(Matchup::new(team("c"), team("d"), 0, 0, false));
+        division.bracket[1][0] = Some(Matchup::new(team("a"), team("c"), 0, 0, false));
+        division.bracket[2][0] = Some(Matchup::new(team("a"), team("e"), 0, 0, false));
+
+        let before = division.clone();
+        division.bracket[0][1] = played("c", "d", 0, 2);
+        advance_changed_teams(&format, &before, &mut division);
+        assert_eq!(teams_at(&division, BracketPosition::upper(1, 0)), (team("a"), team("d")));
+        assert_eq!(teams_at(&division, BracketPosition::upper(2, 0)), (team("a"), team("e")));
+
+        // A correction takes the team back out again
+        let before = division.clone();
+        division.bracket[0][1].as_mut().unwrap().completed = false;
+        advance_changed_teams(&format, &before, &mut division);
+        assert_eq!(teams_at(&division, BracketPosition::upper(1, 0)), (team("a"), None));
+        assert_eq!(teams_at(&division, BracketPosition::upper(2, 0)), (team("a"), team("e")));
+
+        // Rebuilding from results takes out everything that wasn't sent there
+        advance_teams(&format, &mut division);
+        assert_eq!(teams_at(&division, BracketPosition::upper(1, 0)), (None, None));
+    }
+
+    #[test]
+    fn corrections_undo_later_results() {
+        let format = BracketFormat::SingleElimination;
+        let mut division = empty_bracket(2);
+        division.bracket[0][0] = played("a", "b", 2, 0);
+        division.bracket[0][1] = played("c", "d", 2, 0);
+        advance_teams(&format, &mut division);
+        division.bracket[1][0].as_mut().unwrap().team1_score = 2;
+        division.bracket[1][0].as_mut().unwrap().completed = true;
+
+        let before = division.clone();
+        division.bracket[0][0] = played("a", "b", 0, 2);
+        advance_changed_teams(&format, &before, &mut division);
+        let final_matchup = division.bracket[1][0].as_ref().unwrap();
+        assert_eq!((final_matchup.team1.clone(), final_matchup.team2.clone()), (team("b"), team("c")));
+        assert!(!final_matchup.completed);
+    }
+
+    /// Plays every matchup that's ready, in order, until there are none
+    /// left, with team 1 winning if `team1_wins` and team 2 otherwise.
+    /// Returns how many matchups were played.
+    fn play_out(format: &BracketFormat, division: &mut Division, team1_wins: bool) -> usize {
+        let mut played_count = 0;
+        loop {
+            let next = positions(format, division).into_iter()
+                .find(|&position| division.bracket_matchup(position)
+                    .is_some_and(|matchup| matchup.is_filled() && !matchup.completed));
+            let Some(position) = next else {
+                return played_count;
+            };
+            let before = division.clone();
+            let matchup = division.bracket_entry_mut(position).unwrap().as_mut().unwrap();
+            (matchup.team1_score, matchup.team2_score) = if team1_wins { (2, 0) } else { (0, 2) };
+            matchup.completed = true;
+            advance_changed_teams(format, &before, division);
+            played_count += 1;
+        }
+    }
+
+    /// A bracket seeded with teams whose IDs are `seed1`, `seed2` and so on.
+    fn seeded(team_count: usize, format: BracketFormat) -> SaveData {
+        let mut data = SaveData::default();
+        data.settings.bracket_format = format;
+        data.division.teams = (1..=team_count)
+            .map(|seed| Team { id: format!("seed{}", seed), ..Team::default() })
+            .collect();
+        let seeds: Vec<Id> = data.division.teams.iter().map(|team| team.id.clone()).collect();
+        data.seed_bracket(&seeds).unwrap();
+        data
+    }
+
+    /// Checks that no team is in two places at once in any stage, and that
+    /// every team other than the champion was knocked out with two losses
+    /// (or one, for the upper bracket's team losing a grand final that isn't reset).
+    fn check_double_elimination(division: &Division, team_count: usize) {
+        let stages = division.bracket.iter().chain(&division.lower_bracket);
+        for stage in stages {
+            let mut teams: Vec<&Id> = stage.iter().flatten()
+                .flat_map(|matchup| matchup.team1.iter().chain(&matchup.team2))
+                .collect();
+            let team_total = teams.len();
+            teams.sort();
+            teams.dedup();
+            assert_eq!(teams.len(), team_total, "a team is in a stage twice: {:?}", stage);
+        }
+
+        let mut losses: HashMap<&Id, usize> = HashMap::new();
+        for matchup in division.bracket_matchups().filter(|matchup| matchup.completed) {
+            if let Some(loser) = matchup.loser() {
+                *losses.entry(loser).or_insert(0) += 1;
+            }
+        }
+        let last = division.grand_final_reset.as_ref()
+            .filter(|reset| reset.completed)
+            .or(division.grand_final.as_ref())
+            .unwrap();
+        let champion = last.winner().unwrap();
+        assert!(losses.get(champion).copied().unwrap_or(0) < 2);
+        assert_eq!(losses.len(), team_count - 1 + usize::from(losses.contains_key(champion)));
+        for (team, count) in losses {
+            let upper_finalist_without_reset = division.grand_final_reset.is_none()
+                && division.grand_final.as_ref().unwrap().team1.as_ref() == Some(team);
+            if team != champion && !upper_finalist_without_reset {
+                assert_eq!(count, 2, "{} lost {} times", team, count);
+            }
+        }
+    }
+
+    #[test]
+    fn losers_drop_into_the_lower_bracket() {
+        let format = BracketFormat::DoubleElimination { bracket_reset: false };
+        // 8 teams: the first stage's losers pair up, and upper stage 2 drops in reverse
+        assert_eq!(loser_destination(&format, 3, BracketPosition::upper(0, 3)),
+            Some(BracketSlot::new(BracketPosition::lower(0, 1), 2)));
+        assert_eq!(loser_destination(&format, 3, BracketPosition::upper(1, 0)),
+            Some(BracketSlot::new(BracketPosition::lower(1, 1), 2)));
+        assert_eq!(loser_destination(&format, 3, BracketPosition::upper(1, 1)),
+            Some(BracketSlot::new(BracketPosition::lower(1, 0), 2)));
+        assert_eq!(loser_destination(&format, 3, BracketPosition::upper(2, 0)),
+            Some(BracketSlot::new(BracketPosition::lower(3, 0), 2)));
+        assert_eq!(winner_destination(&format, 3, BracketPosition::lower(0, 1)),
+            Some(BracketSlot::new(BracketPosition::lower(1, 1), 1)));
+        assert_eq!(winner_destination(&format, 3, BracketPosition::lower(1, 1)),
+            Some(BracketSlot::new(BracketPosition::lower(2, 0), 2)));
+        assert_eq!(winner_destination(&format, 3, BracketPosition::lower(3, 0)),
+            Some(BracketSlot::new(BracketPosition::grand_final(), 2)));
+        assert_eq!(winner_destination(&format, 3, BracketPosition::upper(2, 0)),
+            Some(BracketSlot::new(BracketPosition::grand_final(), 1)));
+        // Nothing drops down in single elimination
+        assert_eq!(loser_destination(&BracketFormat::SingleElimination, 3, BracketPosition::upper(0, 0)), None);
+    }
+
+    #[test]
+    fn four_team_double_elimination_plays_to_the_end() {
+        let format = BracketFormat::DoubleElimination { bracket_reset: true };
+        let mut division = seeded(4, format.clone()).division;
+        // Team 2 always winning sends the lower bracket's team through the
+        // grand final, so the reset is played too
+        assert_eq!(play_out(&format, &mut division, false), 7);
+        check_double_elimination(&division, 4);
+
+        let grand_final = division.grand_final.as_ref().unwrap();
+        let reset = division.grand_final_reset.as_ref().unwrap();
+        assert_eq!((&reset.team1, &reset.team2), (&grand_final.team1, &grand_final.team2));
+        assert!(reset.completed);
+    }
+
+    #[test]
+    fn four_team_grand_final_without_reset() {
+        let format = BracketFormat::DoubleElimination { bracket_reset: true };
+        let mut division = seeded(4, format.clone()).division;
+        // The upper bracket's team wins the grand final, so there's no reset
+        assert_eq!(play_out(&format, &mut division, true), 6);
+        check_double_elimination(&division, 4);
+        assert!(division.grand_final_reset.is_none());
+        assert_eq!(division.grand_final.as_ref().unwrap().winner(), team("seed1").as_ref());
+    }
+
+    #[test]
+    fn eight_team_double_elimination_plays_to_the_end() {
+        let format = BracketFormat::DoubleElimination { bracket_reset: false };
+        let mut division = seeded(8, format.clone()).division;
+        assert_eq!(play_out(&format, &mut division, true), 14);
+        check_double_elimination(&division, 8);
+
+        // Seeds 5-8 lose first and play each other in the first lower stage
+        assert_eq!(teams_at(&division, BracketPosition::lower(0, 0)), (team("seed8"), team("seed5")));
+        assert_eq!(teams_at(&division, BracketPosition::lower(0, 1)), (team("seed7"), team("seed6")));
+        let grand_final = division.grand_final.as_ref().unwrap();
+        assert_eq!(grand_final.team1, team("seed1"));
+        assert!(grand_final.completed);
+    }
+
+    #[test]
+    fn seed_order_keeps_top_seeds_apart() {
+        assert_eq!(seed_order(1), vec![0, 1]);
+        assert_eq!(seed_order(2), vec![0, 3, 1, 2]);
+        assert_eq!(seed_order(3), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+    }
+
+    #[test]
+    fn seeding_gives_top_seeds_byes() {
+        let format = BracketFormat::SingleElimination;
+
+        let data = seeded(3, format.clone());
+        assert_eq!(data.division.bracket.len(), 2);
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(0, 0)), (team("seed1"), None));
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(0, 1)), (team("seed2"), team("seed3")));
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(1, 0)), (team("seed1"), None));
+
+        let data = seeded(5, format.clone());
+        assert_eq!(data.division.bracket.len(), 3);
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(0, 1)), (team("seed4"), team("seed5")));
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(1, 0)), (team("seed1"), None));
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(1, 1)), (team("seed2"), team("seed3")));
+
+        let data = seeded(6, format.clone());
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(0, 3)), (team("seed3"), team("seed6")));
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(1, 0)), (team("seed1"), None));
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(1, 1)), (team("seed2"), None));
+
+        let data = seeded(8, format);
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(0, 0)), (team("seed1"), team("seed8")));
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(0, 1)), (team("seed4"), team("seed5")));
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(0, 2)), (team("seed2"), team("seed7")));
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(0, 3)), (team("seed3"), team("seed6")));
+        assert!(data.division.bracket[1].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn seeded_single_elimination_plays_to_the_end() {
+        let format = BracketFormat::SingleElimination;
+        for team_count in [3, 5, 6, 8] {
+            let mut division = seeded(team_count, format.clone()).division;
+            // Byes don't need playing
+            assert_eq!(play_out(&format, &mut division, true), team_count - 1);
+            let final_position = BracketPosition::upper(division.bracket.len() - 1, 0);
+            assert_eq!(teams_at(&division, final_position), (team("seed1"), team("seed2")), "{} teams", team_count);
+        }
+    }
+
+    #[test]
+    fn lower_bracket_byes_wait_for_their_team() {
+        let format = BracketFormat::DoubleElimination { bracket_reset: false };
+        let data = seeded(5, format.clone());
+        // Both of these teams had byes, so nobody drops down from them
+        let empty = data.division.bracket_matchup(BracketPosition::lower(0, 1)).unwrap();
+        assert!(empty.completed && empty.team1.is_none() && empty.team2.is_none());
+        // Waiting on the loser of seed4 and seed5, with nobody on the other side
+        let waiting = data.division.bracket_matchup(BracketPosition::lower(0, 0)).unwrap();
+        assert!(!waiting.completed && !waiting.is_filled());
+        // The empty matchup sends nobody here, so this one waits for the loser
+        // of upper(1, 0) and then passes them straight through
+        let next = data.division.bracket_matchup(BracketPosition::lower(1, 1)).unwrap();
+        assert!(!next.completed && !next.is_filled());
+
+        let mut division = data.division.clone();
+        for position in [BracketPosition::upper(0, 1), BracketPosition::upper(1, 0)] {
+            let before = division.clone();
+            let matchup = division.bracket_entry_mut(position).unwrap().as_mut().unwrap();
+            (matchup.team1_score, matchup.completed) = (2, true);
+            advance_changed_teams(&format, &before, &mut division);
+        }
+        let next = division.bracket_matchup(BracketPosition::lower(1, 1)).unwrap();
+        assert!(next.completed);
+        assert_eq!((next.team1.clone(), next.team2.clone()), (None, team("seed4")));
+    }
+
+    #[test]
+    fn seeded_double_elimination_plays_to_the_end() {
+        let format = BracketFormat::DoubleElimination { bracket_reset: false };
+        for team_count in [3, 5, 6, 8] {
+            let mut division = seeded(team_count, format.clone()).division;
+            assert_eq!(play_out(&format, &mut division, true), team_count * 2 - 2, "{} teams", team_count);
+            check_double_elimination(&division, team_count);
+            let grand_final = division.grand_final.as_ref().unwrap();
+            assert_eq!(grand_final.team1, team("seed1"));
+            assert!(grand_final.completed);
+        }
+
+        // Seed 5 loses to seed 4, then makes it through the lower bracket byes
+        let mut division = seeded(5, format.clone()).division;
+        play_out(&format, &mut division, true);
+        assert_eq!(teams_at(&division, BracketPosition::lower(0, 0)), (None, team("seed5")));
+        assert_eq!(teams_at(&division, BracketPosition::lower(1, 0)), (team("seed5"), team("seed3")));
+        assert_eq!(teams_at(&division, BracketPosition::lower(1, 1)), (None, team("seed4")));
+        assert_eq!(division.grand_final.as_ref().unwrap().team2, team("seed5"));
+    }
+
+    #[test]
+    fn resizing_keeps_matchups_and_results() {
+        let mut data = SaveData::default();
+        let first = played("a", "b", 2, 0);
+        data.division.bracket[0][0] = first.clone();
+        data.division.bracket[0][1] = played("c", "d", 0, 2);
+        advance_teams(&data.settings.bracket_format, &mut data.division);
+        let second = data.division.bracket[1][0].clone();
+        assert_eq!(teams_at(&data.division, BracketPosition::upper(1, 0)), (team("a"), team("d")));
+
+        // Stages are added after the last one, so the first stage stays first
+        assert!(data.preview_bracket_resize(4).unwrap().discarded.is_empty());
+        data.resize_bracket(4).unwrap();
+        assert_eq!(data.division.bracket.iter().map(Vec::len).collect::<Vec<_>>(), vec![8, 4, 2, 1]);
+        assert_eq!(data.division.bracket[0][0], first);
+        assert_eq!(data.division.bracket[1][0], second);
+        assert_eq!(data.settings.bracket_stage_count, 4);
+
+        data.resize_bracket(2).unwrap();
+        assert_eq!(data.division.bracket.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(data.division.bracket[0][0], first);
+        assert_eq!(data.division.bracket[1][0], second);
+    }
+
+    #[test]
+    fn resize_preview_lists_what_would_be_discarded() {
+        let mut data = SaveData::default();
+        data.division.bracket[0][0] = played("a", "b", 2, 0);
+        data.division.bracket[0][2] = Some(Matchup::default());
+        data.division.bracket[0][3] = Some(Matchup::new(team("c"), None, 0, 0, false));
+
+        // The empty matchup doesn't count as losing anything
+        let preview = data.preview_bracket_resize(2).unwrap();
+        assert_eq!(preview.stage_count, 2);
+        assert_eq!(preview.discarded, vec![BracketPosition::upper(0, 3)]);
+        assert_eq!(data.division.bracket.len(), 3);
+
+        data.settings.bracket_format = BracketFormat::DoubleElimination { bracket_reset: false };
+        data.resize_bracket(3).unwrap();
+        data.division.lower_bracket[0][1] = Some(Matchup::new(team("z"), None, 0, 0, false));
+        let preview = data.preview_bracket_resize(2).unwrap();
+        assert_eq!(preview.discarded, vec![BracketPosition::upper(0, 3), BracketPosition::lower(0, 1)]);
+    }
+
+    #[test]
+    fn oversized_brackets_are_refused() {
+        let mut data = SaveData::default();
+        assert!(data.preview_bracket_resize(MAX_STAGE_COUNT + 1).is_err());
+        assert!(data.resize_bracket(65).is_err());
+        assert_eq!(data.division.bracket.len(), 3);
+        data.resize_bracket(MAX_STAGE_COUNT).unwrap();
+        assert_eq!(data.division.bracket[0].len(), 1 << (MAX_STAGE_COUNT - 1));
+        data.settings.bracket_stage_count = 30;
+        assert!(data.settings.validate().is_err());
+    }
+
+    #[test]
+    fn new_settings_resize_the_bracket_and_rounds() {
+        let mut data = SaveData::default();
+        let first = played("a", "b", 2, 0);
+        data.division.bracket[0][0] = first.clone();
+        let settings = Settings { bracket_stage_count: 2, round_count: 3, ..data.settings.clone() };
+        data.replace_settings(settings).unwrap();
+        assert_eq!(data.division.bracket.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(data.division.bracket[0][0], first);
+        assert_eq!(data.current_match.rounds.len(), 3);
+
+        let settings = Settings { bracket_stage_count: MAX_STAGE_COUNT + 1, ..data.settings.clone() };
+        assert!(data.replace_settings(settings).is_err());
+        assert_eq!(data.settings.bracket_stage_count, 2);
+    }
+}
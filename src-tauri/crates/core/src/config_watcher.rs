@@ -0,0 +1,262 @@
+use notify_debouncer_mini::{
+    new_debouncer,
+    notify::{self, RecommendedWatcher, RecursiveMode},
+    DebounceEventResult,
+    Debouncer,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeSet, path::{Path, PathBuf}, time::Duration};
+
+use crate::{
+    ProjectState,
+    SharedState,
+    error::{CommandError, CommandResult},
+    fs,
+    models::SaveData,
+    updates::{DataSection, UpdateOrigin},
+};
+
+// Scripts and editors tend to write files in several steps, so wait for things to settle
+const CONFIG_CHANGE_DELAY: Duration = Duration::from_millis(500);
+
+/// The loaded config was changed by something other than the app.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExternalChange {
+    pub path: String,
+    /// The app has unsaved changes too, so picking either side loses the other's edits
+    pub conflict: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalChangeAction {
+    /// Replace the app's data with the file's
+    Reload,
+    /// Take the file's edits wherever the app hasn't changed the same thing
+    Merge,
+    /// Keep the app's data, which will replace the file on the next save
+    Ignore,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MergeReport {
+    /// Everything both sides changed, e.g. `settings.event_name`, where the app's version was kept
+    pub conflicts: Vec<String>,
+}
+
+/// Watches the loaded config's folder rather than the file itself, since
+/// saving replaces the file instead of writing to it.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    pub path: PathBuf,
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+pub fn watch_config(shared_state: SharedState, path: &Path) -> notify::Result<ConfigWatcher> {
+    let file_name = path.file_name().map(|name| name.to_os_string());
+    let mut debouncer = new_debouncer(CONFIG_CHANGE_DELAY, move |result: DebounceEventResult| {
+        match result {
+            Ok(events) => {
+                if events.iter().any(|event| event.path.file_name() == file_name.as_deref()) {
+                    check_for_external_change(&mut shared_state.lock().unwrap());
+                }
+            },
+            Err(err) => eprintln!("Error watching config file: {:?}", err),
+        }
+    })?;
+    debouncer.watcher().watch(&fs::remove_file_from_path(path), RecursiveMode::NonRecursive)?;
+    Ok(ConfigWatcher {
+        path: path.to_path_buf(),
+        _debouncer: debouncer,
+    })
+}
+
+/// Lets everyone know if the loaded config no longer holds what the app last
+/// loaded or saved, which also means the app's own saves are never reported.
+pub fn check_for_external_change(state: &mut ProjectState) {
+    let Some(path) = state.loaded_config.clone() else {
+        return;
+    };
+    // The file can briefly be missing while something else replaces it
+    let Ok(contents) = std::fs::read(&path) else {
+        return;
+    };
+    if state.saved_contents_hash == Some(fs::contents_hash(&contents)) {
+        return;
+    }
+
+    println!("Config file changed outside of the app: {:?}", path);
+    let conflict = state.is_dirty();
+    state.set_external_change(Some(ExternalChange {
+        path: path.to_string_lossy().to_string(),
+        conflict,
+    }));
+}
+
+pub fn resolve_external_change(state: &mut ProjectState, action: ExternalChangeAction) -> CommandResult<MergeReport> {
+    let path = state.loaded_config.clone().ok_or(CommandError::NoConfigLoaded)?;
+    let disk = fs::read_config_file(&path)?;
+    state.saved_contents_hash = Some(fs::contents_hash(disk.contents.as_bytes()));
+
+    match action {
+        ExternalChangeAction::Reload => {
+            // Going through the history means a reload can be undone
+            let data = disk.data;
+            state.update_section(DataSection::All, UpdateOrigin::Desktop, |current| *current = data);
+            state.mark_saved();
+            Ok(MergeReport::default())
+        },
+        ExternalChangeAction::Merge => {
+            let base = state.saved_data.clone().unwrap_or_else(|| state.data.clone());
+            let mut conflicts = Vec::new();
+            let merged = merge_values(
+                &serde_json::to_value(&base).unwrap(),
+                &serde_json::to_value(&state.data).unwrap(),
+                &serde_json::to_value(&disk.data).unwrap(),
+                "",
+                &mut conflicts,
+            );
+            let merged = serde_json::from_value::<SaveData>(merged)
+                .map_err(|err| CommandError::validation(format!("Could not merge changes: {}", err)))?;
+
+            state.update_section(DataSection::All, UpdateOrigin::Desktop, |current| *current = merged);
+            if state.data == disk.data {
+                state.mark_saved();
+            } else {
+                state.mark_out_of_sync(disk.data);
+            }
+            Ok(MergeReport { conflicts })
+        },
+        ExternalChangeAction::Ignore => {
+            state.mark_out_of_sync(disk.data);
+            Ok(MergeReport::default())
+        },
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn item_ids(items: &[Value]) -> Vec<Option<&Value>> {
+    items.iter().map(|item| item.get("id")).collect()
+}
+
+/// Three way merge of JSON values, taking whichever side changed something
+/// from `base`. Where both sides changed the same thing differently, `ours`
+/// is kept and the path is added to `conflicts`.
+fn merge_values(base: &Value, ours: &Value, theirs: &Value, path: &str, conflicts: &mut Vec<String>) -> Value {
+    if ours == theirs || theirs == base {
+        return ours.clone();
+    }
+    if ours == base {
+        return theirs.clone();
+    }
+
+    match (base, ours, theirs) {
+        (Value::Object(base), Value::Object(ours), Value::Object(theirs)) => {
+            let keys: BTreeSet<&String> = ours.keys().chain(theirs.keys()).collect();
+            let merged = keys.into_iter()
+                .filter_map(|key| {
+                    let value = merge_values(
+                        base.get(key).unwrap_or(&Value::Null),
+                        ours.get(key).unwrap_or(&Value::Null),
+                        theirs.get(key).unwrap_or(&Value::Null),
+                        &join_path(path, key),
+                        conflicts,
+                    );
+                    // A key that one side removed stays removed
+                    let removed = value.is_null() && !(ours.contains_key(key) && theirs.contains_key(key));
+                    (!removed).then(|| (key.clone(), value))
+                })
+                .collect();
+            Value::Object(merged)
+        },
+        // Lists can only be merged item by item while they still line up
+        (Value::Array(base), Value::Array(ours), Value::Array(theirs))
+            if item_ids(base) == item_ids(ours) && item_ids(base) == item_ids(theirs) =>
+        {
+            let merged = base.iter().zip(ours).zip(theirs)
+                .enumerate()
+                .map(|(i, ((base, ours), theirs))| {
+                    merge_values(base, ours, theirs, &format!("{}[{}]", path, i), conflicts)
+                })
+                .collect();
+            Value::Array(merged)
+        },
+        _ => {
+            conflicts.push(path.to_string());
+            ours.clone()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn merge(base: Value, ours: Value, theirs: Value) -> (Value, Vec<String>) {
+        let mut conflicts = Vec::new();
+        let merged = merge_values(&base, &ours, &theirs, "", &mut conflicts);
+        (merged, conflicts)
+    }
+
+    #[test]
+    fn one_sided_changes_are_taken() {
+        let base = json!({ "event_name": "Cup", "round_count": 5 });
+        let changed = json!({ "event_name": "Cup Finals", "round_count": 5 });
+        assert_eq!(merge(base.clone(), changed.clone(), base.clone()), (changed.clone(), vec![]));
+        assert_eq!(merge(base.clone(), base.clone(), changed.clone()), (changed, vec![]));
+    }
+
+    #[test]
+    fn changes_to_different_keys_are_combined() {
+        let base = json!({ "event_name": "Cup", "round_count": 5 });
+        let ours = json!({ "event_name": "Cup Finals", "round_count": 5 });
+        let theirs = json!({ "event_name": "Cup", "round_count": 3 });
+        assert_eq!(merge(base, ours, theirs), (json!({ "event_name": "Cup Finals", "round_count": 3 }), vec![]));
+    }
+
+    #[test]
+    fn conflicting_changes_keep_ours() {
+        let base = json!({ "settings": { "event_name": "Cup", "round_count": 5 } });
+        let ours = json!({ "settings": { "event_name": "Ours", "round_count": 5 } });
+        let theirs = json!({ "settings": { "event_name": "Theirs", "round_count": 3 } });
+        let (merged, conflicts) = merge(base, ours, theirs);
+        assert_eq!(merged, json!({ "settings": { "event_name": "Ours", "round_count": 3 } }));
+        assert_eq!(conflicts, vec![String::from("settings.event_name")]);
+    }
+
+    #[test]
+    fn lists_merge_item_by_item_while_they_line_up() {
+        let base = json!({ "teams": [{ "id": "a", "name": "A" }, { "id": "b", "name": "B" }] });
+        let ours = json!({ "teams": [{ "id": "a", "name": "Alpha" }, { "id": "b", "name": "B" }] });
+        let theirs = json!({ "teams": [{ "id": "a", "name": "A" }, { "id": "b", "name": "Bravo" }] });
+        let (merged, conflicts) = merge(base.clone(), ours.clone(), theirs);
+        assert_eq!(merged, json!({ "teams": [{ "id": "a", "name": "Alpha" }, { "id": "b", "name": "Bravo" }] }));
+        assert!(conflicts.is_empty());
+
+        // Once both sides reorder or add items, the whole list is a conflict
+        let reordered = json!({ "teams": [{ "id": "b", "name": "B" }, { "id": "a", "name": "A" }] });
+        let added = json!({ "teams": [{ "id": "a", "name": "A" }, { "id": "b", "name": "B" }, { "id": "c", "name": "C" }] });
+        let (merged, conflicts) = merge(base, reordered.clone(), added);
+        assert_eq!(merged, reordered);
+        assert_eq!(conflicts, vec![String::from("teams")]);
+    }
+
+    #[test]
+    fn keys_added_or_removed_on_one_side_are_kept_that_way() {
+        let base = json!({ "event_name": "Cup", "api_token": "secret" });
+        let added = json!({ "event_name": "Cup", "api_token": "secret", "autosave_interval": 60 });
+        let removed = json!({ "event_name": "Cup" });
+        assert_eq!(merge(base.clone(), base.clone(), added.clone()), (added.clone(), vec![]));
+        assert_eq!(merge(base.clone(), removed.clone(), base.clone()), (removed.clone(), vec![]));
+        assert_eq!(merge(base.clone(), added, removed), (json!({ "event_name": "Cup", "autosave_interval": 60 }), vec![]));
+    }
+}
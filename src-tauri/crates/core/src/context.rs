@@ -0,0 +1,168 @@
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use crate::{
+    bracket::{self, BracketPosition, BracketSide, BracketSlot},
+    models::{BracketFormat, Id, Matchup, SaveData},
+    stages,
+};
+
+/// The values exposed to templates (and the JSON API), grouped by the part
+/// of the save data they come from.
+pub type ContextMap = Map<String, Value>;
+
+fn into_map(value: Value) -> ContextMap {
+    match value {
+        Value::Object(map) => map,
+        _ => unreachable!("context sections are always built from objects"),
+    }
+}
+
+/// Lets templates look things up by the IDs that everything references them by,
+/// e.g. `teams_by_id[matchup.team1]`.
+fn by_id<'a, T: Serialize + 'a>(
+    items: impl IntoIterator<Item = &'a T>,
+    id: impl Fn(&T) -> &Id,
+) -> HashMap<&'a Id, &'a T> {
+    items.into_iter().map(|item| (id(item), item)).collect()
+}
+
+pub fn settings_context(data: &SaveData) -> ContextMap {
+    into_map(json!({
+        "event_name": data.settings.event_name,
+        "round_count": data.settings.round_count,
+        "bracket_stage_count": data.settings.bracket_stage_count,
+        "match_format": data.settings.match_format,
+        "gamemodes": data.settings.gamemodes,
+        "roles": data.settings.roles,
+        "characters": data.settings.characters,
+        "gamemodes_by_id": by_id(&data.settings.gamemodes, |gamemode| &gamemode.id),
+        "maps_by_id": by_id(
+            data.settings.gamemodes.iter().flat_map(|gamemode| &gamemode.maps),
+            |map| &map.id,
+        ),
+        "roles_by_id": by_id(&data.settings.roles, |role| &role.id),
+        "characters_by_id": by_id(&data.settings.characters, |character| &character.id),
+    }))
+}
+
+/// Where both sides of every matchup come from, laid out the same way as the
+/// matchups themselves, e.g. `bracket_feeds.lower[1][0].team2` is the team
+/// dropping down from the upper bracket into the lower bracket's second stage.
+fn bracket_feeds(data: &SaveData, lower_bracket: &[Vec<Option<Matchup>>]) -> Value {
+    let feeds = bracket::feeds(&data.settings.bracket_format, &data.division);
+    let slot_feeds = |position: BracketPosition| json!({
+        "team1": feeds.get(&BracketSlot::new(position, 1)),
+        "team2": feeds.get(&BracketSlot::new(position, 2)),
+    });
+    let stage_feeds = |side: BracketSide, stages: &[Vec<Option<Matchup>>]| -> Vec<Vec<Value>> {
+        stages.iter().enumerate()
+            .map(|(stage, matchups)| (0..matchups.len())
+                .map(|index| slot_feeds(BracketPosition::new(side, stage, index)))
+                .collect())
+            .collect()
+    };
+
+    json!({
+        "upper": stage_feeds(BracketSide::Upper, &data.division.bracket),
+        "lower": stage_feeds(BracketSide::Lower, lower_bracket),
+        "grand_final": slot_feeds(BracketPosition::grand_final()),
+    })
+}
+
+/// The standings table of every stage, best team first, along with the
+/// rounds played so far, e.g. `standings[0].table[0].team` is the ID of the
+/// team leading the first stage.
+fn standings(data: &SaveData) -> Vec<Value> {
+    data.division.stages.iter()
+        .map(|stage| json!({
+            "id": stage.id,
+            "name": stage.name,
+            "format": stage.format,
+            "rounds": stage.rounds,
+            "table": stages::standings(stage),
+        }))
+        .collect()
+}
+
+pub fn division_context(data: &SaveData) -> ContextMap {
+    let division = &data.division;
+    // Only what the bracket format uses, so templates can draw whatever is there
+    let (lower_bracket, grand_final, grand_final_reset) = match data.settings.bracket_format {
+        BracketFormat::SingleElimination => (&[][..], None, None),
+        BracketFormat::DoubleElimination { bracket_reset } => (
+            &division.lower_bracket[..],
+            division.grand_final.as_ref(),
+            division.grand_final_reset.as_ref().filter(|_| bracket_reset),
+        ),
+    };
+
+    into_map(json!({
+        "teams": division.teams,
+        "teams_by_id": by_id(&division.teams, |team| &team.id),
+        "team_count": data.team_names().len(),
+        "bracket": division.bracket,
+        "bracket_format": data.settings.bracket_format,
+        "lower_bracket": lower_bracket,
+        "grand_final": grand_final,
+        "grand_final_reset": grand_final_reset,
+        "bracket_feeds": bracket_feeds(data, lower_bracket),
+        "stages": division.stages,
+        "standings": standings(data),
+    }))
+}
+
+pub fn resources_context(data: &SaveData) -> ContextMap {
+    into_map(json!({
+        "images": data.images_hashmap(),
+        "strings": data.strings_hashmap(),
+    }))
+}
+
+pub fn match_context(data: &SaveData) -> ContextMap {
+    let current_match = &data.current_match;
+    let team1 = current_match.team1.as_deref().and_then(|id| data.division.team(id));
+    let team2 = current_match.team2.as_deref().and_then(|id| data.division.team(id));
+    let result = current_match.result(&data.settings.match_format);
+    into_map(json!({
+        "rounds": current_match.rounds,
+        "team1": team1,
+        "team2": team2,
+        "team1_score": result.team1_score,
+        "team2_score": result.team2_score,
+        "match_decided": result.decided,
+        "match_winner": result.winner,
+        "match_draw": result.draw,
+        "moot_rounds": result.moot_rounds,
+        "swap_scoreboard": current_match.swap_scoreboard,
+        "matchup": current_match.matchup.as_deref().and_then(|id| data.division.matchup(id)),
+    }))
+}
+
+/// Everything at once, plus every resource as its own `image_*`/`string_*` value.
+pub fn full_context(data: &SaveData) -> ContextMap {
+    let mut context = ContextMap::new();
+    context.extend(settings_context(data));
+    context.extend(division_context(data));
+    context.extend(resources_context(data));
+    context.extend(match_context(data));
+
+    for image in &data.resources.images {
+        let name = format!("image_{}", image.name);
+        context.insert(name, json!(image.value));
+    }
+
+    for string in &data.resources.strings {
+        let name = format!("string_{}", string.name);
+        context.insert(name, json!(string.value));
+    }
+
+    context
+}
+
+pub fn populate_context(context: &mut tera::Context, data: &SaveData) {
+    for (name, value) in full_context(data) {
+        context.insert(name, &value);
+    }
+}
@@ -0,0 +1,78 @@
+use serde::Serialize;
+use std::{fmt, io, path::Path};
+
+/// Errors from working with a project, returned as is from Tauri commands and
+/// serialized so the UI can tell the operator what actually went wrong.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandError {
+    /// Reading or writing a file failed
+    Io { path: String, message: String },
+    /// A config file isn't valid JSON or doesn't fit the project format. The
+    /// position is only known when the problem can be traced back to the file.
+    Parse { path: String, message: String, line: Option<usize>, column: Option<usize> },
+    /// The project or a setting doesn't allow what was asked for
+    Validation { message: String },
+    /// The webserver couldn't listen on its address
+    WebserverBind { address: String, message: String },
+    /// The webserver failed for some other reason
+    Webserver { message: String },
+    /// The project's templates couldn't be loaded
+    Template { message: String },
+    NoConfigLoaded,
+    WebserverAlreadyRunning,
+}
+
+pub type CommandResult<T> = Result<T, CommandError>;
+
+impl CommandError {
+    pub fn io(path: &Path, err: io::Error) -> Self {
+        Self::Io {
+            path: path.to_string_lossy().to_string(),
+            message: err.to_string(),
+        }
+    }
+
+    pub fn parse(path: &Path, err: serde_json::Error) -> Self {
+        // serde_json reports line 0 when the error isn't tied to a position
+        let (line, column) = match err.line() {
+            0 => (None, None),
+            line => (Some(line), Some(err.column())),
+        };
+        Self::Parse {
+            path: path.to_string_lossy().to_string(),
+            message: err.to_string(),
+            line,
+            column,
+        }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation { message: message.into() }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Io { path, message } =>
+                write!(f, "Could not access {}: {}", path, message),
+            CommandError::Parse { path, message, .. } =>
+                write!(f, "Could not read {}: {}", path, message),
+            CommandError::Validation { message } =>
+                write!(f, "{}", message),
+            CommandError::WebserverBind { address, message } =>
+                write!(f, "Failed to bind webserver to {}: {}", address, message),
+            CommandError::Webserver { message } =>
+                write!(f, "Webserver error: {}", message),
+            CommandError::Template { message } =>
+                write!(f, "Failed to load templates: {}", message),
+            CommandError::NoConfigLoaded =>
+                write!(f, "No config loaded"),
+            CommandError::WebserverAlreadyRunning =>
+                write!(f, "Webserver is already running"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
@@ -0,0 +1,163 @@
+use std::{fs, hash::{DefaultHasher, Hash, Hasher}, io::Write};
+use crate::{ProjectState, backups, error::{CommandError, CommandResult}, migrations, models::{SaveData, SAVE_DATA_VERSION}, validation};
+
+/// The contents of a config file from an older version, kept from when it
+/// was loaded so they can be backed up before the upgraded data replaces them.
+#[derive(Debug, Clone)]
+pub struct OriginalConfig {
+    pub version: u32,
+    pub contents: String,
+}
+
+/// A config file as it was read from disk, already upgraded to the current version.
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub data: SaveData,
+    pub version: u32,
+    pub contents: String,
+}
+
+pub fn read_config_file(path: &std::path::Path) -> CommandResult<ConfigFile> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| CommandError::io(path, err))?;
+    let mut value = serde_json::from_str::<serde_json::Value>(&contents)
+        .map_err(|err| CommandError::parse(path, err))?;
+    let version = migrations::migrate(&mut value)
+        .map_err(CommandError::validation)?;
+
+    // An upgraded file no longer lines up with its text, but a current one
+    // can be read straight from it so errors point at the right line
+    let mut data = if version == SAVE_DATA_VERSION {
+        serde_json::from_str::<SaveData>(&contents)
+    } else {
+        serde_json::from_value::<SaveData>(value)
+    }.map_err(|err| CommandError::parse(path, err))?;
+    data.settings.validate().map_err(CommandError::validation)?;
+
+    data.correct_rounds_to_count();
+    Ok(ConfigFile { data, version, contents })
+}
+
+/// Used to tell whether the file on disk is still the one that was last
+/// loaded or saved.
+pub fn contents_hash(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn read_into_state_from_config_file(state: &mut ProjectState, path: &std::path::Path) -> CommandResult<()> {
+    println!("Opening config file: {:?}", path);
+    let ConfigFile { data, version, contents } = read_config_file(path)?;
+
+    for problem in validation::validate(&data) {
+        eprintln!("Problem in config file: {:?}", problem);
+    }
+    if version < SAVE_DATA_VERSION {
+        println!("Upgraded config file from version {} to {}", version, SAVE_DATA_VERSION);
+        // The data can be upgraded, but templates written for it need updating by hand
+        let templates_dir = remove_file_from_path(path).join("templates");
+        for problem in validation::check_templates(&templates_dir) {
+            eprintln!("Template needs updating: {:?}", problem);
+        }
+    }
+    state.saved_contents_hash = Some(contents_hash(contents.as_bytes()));
+    state.original_config = (version < SAVE_DATA_VERSION)
+        .then_some(OriginalConfig { version, contents });
+    state.loaded_config = Some(path.to_path_buf());
+    state.data = data;
+    println!("Config file opened successfully: {:?}", path);
+    Ok(())
+}
+
+/// e.g. `collegiate.json` from version 0 is backed up to `collegiate.v0.backup.json`
+pub fn backup_path_for_version(path: &std::path::Path, version: u32) -> std::path::PathBuf {
+    let stem = path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.v{}.backup.json", stem, version))
+}
+
+pub fn write_state_to_config_file(state: &mut ProjectState, path: &std::path::Path) -> CommandResult<()> {
+    save_config_file(state, path, true)
+}
+
+/// Saves without adding to the file's backups, since frequent autosaves
+/// would quickly push out the ones made by hand.
+pub fn autosave_config_file(state: &mut ProjectState, path: &std::path::Path) -> CommandResult<()> {
+    save_config_file(state, path, false)
+}
+
+fn save_config_file(state: &mut ProjectState, path: &std::path::Path, keep_backup: bool) -> CommandResult<()> {
+    println!("Saving config file: {:?}", path);
+
+    // Never replace a file from an older version without keeping a copy
+    let overwrites_original = state.loaded_config.as_deref() == Some(path);
+    if let Some(original) = state.original_config.as_ref().filter(|_| overwrites_original) {
+        let backup_path = backup_path_for_version(path, original.version);
+        if !backup_path.exists() {
+            write_atomically(&backup_path, original.contents.as_bytes())
+                .map_err(|err| CommandError::io(&backup_path, err))?;
+            println!("Backed up original config file to {:?}", backup_path);
+        }
+    }
+
+    // A failed backup shouldn't stop the save itself
+    if keep_backup {
+        if let Err(err) = backups::back_up(path) {
+            eprintln!("Error backing up config file: {}", err);
+        }
+    }
+
+    let serialized = serde_json::to_string(&state.data).unwrap();
+    write_atomically(path, serialized.as_bytes())
+        .map_err(|err| CommandError::io(path, err))?;
+    state.saved_contents_hash = Some(contents_hash(serialized.as_bytes()));
+    if overwrites_original {
+        state.original_config = None;
+    }
+    state.loaded_config = Some(path.to_path_buf());
+    println!("Config file saved successfully: {:?}", path);
+    Ok(())
+}
+
+/// Writes to a temporary file next to `path` first and then renames it over
+/// the original, so a crash or full disk never leaves a half written file.
+pub fn write_atomically(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+pub fn remove_file_from_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut path = path.to_path_buf();
+    path.pop();
+    path
+}
+
+// prefixed with root! not dot!
+pub fn to_relative_path(base_path: &std::path::Path, path: &std::path::Path) -> Option<String> {
+    path.strip_prefix(base_path).ok()
+        .map(|p| std::path::Path::new(".").join(p))
+        .map(|p| p.to_string_lossy().to_string())
+        .map(|p| p.replace("\\", "/"))
+        .map(|p| p.trim_start_matches(".").to_string())
+}
+
+pub fn from_relative_path(base_path: &std::path::Path, path: &str) -> String {
+    let path = path.trim_start_matches("/");
+    let path = std::path::Path::new(path);
+    base_path.join(path).to_string_lossy().to_string()
+}
@@ -0,0 +1,439 @@
+use serde::Serialize;
+use std::{collections::VecDeque, time::{Duration, Instant}};
+
+use crate::{
+    models::{Division, Match, Matchup, Resources, SaveData, Settings},
+    updates::{DataSection, UpdateOrigin},
+};
+
+/// How many changes can be undone. Each one keeps a copy of every section
+/// it changed from before and after the change, so a change to the whole
+/// project (resizing or seeding the bracket) costs both its settings and its
+/// division twice over, but never the sections it left alone.
+pub const MAX_HISTORY: usize = 100;
+
+/// Changes to the same thing this close together are merged, so typing out
+/// a team name is undone in one go instead of a letter at a time.
+const MERGE_WINDOW: Duration = Duration::from_secs(2);
+
+/// A copy of the part of the data a change touched.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Snapshot {
+    Settings(Settings),
+    Division(Division),
+    Resources(Resources),
+    CurrentMatch(Match),
+    /// One of each of the other sections, in the same order every time
+    Sections(Vec<Snapshot>),
+}
+
+impl Snapshot {
+    /// Templates aren't part of the data, so there's nothing to take for them.
+    pub fn take(data: &SaveData, section: DataSection) -> Option<Self> {
+        match section {
+            DataSection::Settings => Some(Self::Settings(data.settings.clone())),
+            DataSection::Division => Some(Self::Division(data.division.clone())),
+            DataSection::Resources => Some(Self::Resources(data.resources.clone())),
+            DataSection::CurrentMatch => Some(Self::CurrentMatch(data.current_match.clone())),
+            DataSection::All => Some(Self::Sections(vec![
+                Self::Settings(data.settings.clone()),
+                Self::Division(data.division.clone()),
+                Self::Resources(data.resources.clone()),
+                Self::CurrentMatch(data.current_match.clone()),
+            ])),
+            DataSection::Templates => None,
+        }
+    }
+
+    /// Narrows snapshots of several sections down to the ones that changed,
+    /// so the history doesn't hold on to copies of everything else. Returns
+    /// nothing if nothing changed.
+    pub fn diff(before: Self, after: Self) -> Option<(Self, Self)> {
+        match (before, after) {
+            (Self::Sections(before), Self::Sections(after)) => {
+                let (mut before, mut after): (Vec<_>, Vec<_>) = before.into_iter()
+                    .zip(after)
+                    .filter(|(before, after)| before != after)
+                    .unzip();
+                match before.len() {
+                    0 => None,
+                    1 => Some((before.remove(0), after.remove(0))),
+                    _ => Some((Self::Sections(before), Self::Sections(after))),
+                }
+            },
+            (before, after) => (before != after).then_some((before, after)),
+        }
+    }
+
+    pub fn section(&self) -> DataSection {
+        match self {
+            Self::Settings(_) => DataSection::Settings,
+            Self::Division(_) => DataSection::Division,
+            Self::Resources(_) => DataSection::Resources,
+            Self::CurrentMatch(_) => DataSection::CurrentMatch,
+            Self::Sections(_) => DataSection::All,
+        }
+    }
+
+    fn restore(&self, data: &mut SaveData) {
+        match self {
+            Self::Settings(settings) => data.settings = settings.clone(),
+            Self::Division(division) => data.division = division.clone(),
+            Self::Resources(resources) => data.resources = resources.clone(),
+            Self::CurrentMatch(current_match) => data.current_match = current_match.clone(),
+            Self::Sections(sections) => sections.iter().for_each(|section| section.restore(data)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Change {
+    description: String,
+    origin: UpdateOrigin,
+    before: Snapshot,
+    after: Snapshot,
+    time: Instant,
+    /// Only the most recent edit can have more merged into it
+    mergeable: bool,
+}
+
+impl Change {
+    fn summary(&self) -> ChangeSummary {
+        ChangeSummary {
+            description: self.description.clone(),
+            section: self.after.section(),
+            origin: self.origin,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeSummary {
+    pub description: String,
+    pub section: DataSection,
+    pub origin: UpdateOrigin,
+}
+
+/// The changes that can be undone and redone, most recent first.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistorySummary {
+    pub undo: Vec<ChangeSummary>,
+    pub redo: Vec<ChangeSummary>,
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo_stack: VecDeque<Change>,
+    redo_stack: Vec<Change>,
+}
+
+impl History {
+    pub fn record(&mut self, before: Snapshot, after: Snapshot, origin: UpdateOrigin) {
+        let description = describe_change(&before, &after);
+        self.redo_stack.clear();
+
+        if let Some(last) = self.undo_stack.back_mut() {
+            // Typing into a field keeps changing the same thing, so the
+            // change as a whole is described the same as each step of it
+            let merged_description = describe_change(&last.before, &after);
+            let can_merge = last.mergeable
+                && origin == UpdateOrigin::Desktop
+                && last.origin == origin
+                && last.after.section() == after.section()
+                && last.time.elapsed() < MERGE_WINDOW;
+            if can_merge && merged_description == description {
+                last.description = merged_description;
+                last.after = after;
+                last.time = Instant::now();
+                return;
+            }
+        }
+
+        self.undo_stack.push_back(Change {
+            description,
+            origin,
+            before,
+            after,
+            time: Instant::now(),
+            mergeable: true,
+        });
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// Puts back the data from before the most recent change.
+    pub fn undo(&mut self, data: &mut SaveData) -> Option<ChangeSummary> {
+        let change = self.undo_stack.pop_back()?;
+        change.before.restore(data);
+        let summary = change.summary();
+        self.redo_stack.push(change);
+        Some(summary)
+    }
+
+    /// Applies the most recently undone change again.
+    pub fn redo(&mut self, data: &mut SaveData) -> Option<ChangeSummary> {
+        let mut change = self.redo_stack.pop()?;
+        change.after.restore(data);
+        let summary = change.summary();
+        // Don't let an edit right after this get merged into it
+        change.mergeable = false;
+        self.undo_stack.push_back(change);
+        Some(summary)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    pub fn summary(&self) -> HistorySummary {
+        HistorySummary {
+            undo: self.undo_stack.iter().rev().map(Change::summary).collect(),
+            redo: self.redo_stack.iter().rev().map(Change::summary).collect(),
+        }
+    }
+}
+
+/// Lists what differs between two versions of something, e.g.
+/// `["event name", "round count"]`.
+fn changed_fields<'a>(fields: &[(&'a str, bool)]) -> Vec<&'a str> {
+    fields.iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+fn describe_settings(before: &Settings, after: &Settings) -> Vec<String> {
+    changed_fields(&[
+        ("event name", before.event_name != after.event_name),
+        ("round count", before.round_count != after.round_count),
+        ("bracket stage count", before.bracket_stage_count != after.bracket_stage_count),
+        ("gamemodes", before.gamemodes != after.gamemodes),
+        ("roles", before.roles != after.roles),
+        ("characters", before.characters != after.characters),
+        ("API token", before.api_token != after.api_token),
+        ("webserver address", before.webserver != after.webserver),
+        ("match format", before.match_format != after.match_format),
+        ("bracket format", before.bracket_format != after.bracket_format),
+        ("autosave", before.autosave_interval != after.autosave_interval),
+    ]).into_iter().map(String::from).collect()
+}
+
+fn describe_division(before: &Division, after: &Division) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if before.teams.len() != after.teams.len() {
+        changes.push(String::from("teams"));
+    } else if let Some(team) = after.teams.iter()
+        .zip(&before.teams)
+        .find(|(after, before)| after != before)
+        .map(|(team, _)| team)
+    {
+        changes.push(format!("team {}", team.name));
+    }
+
+    describe_bracket("bracket", &before.bracket, &after.bracket, &mut changes);
+    describe_bracket("lower bracket", &before.lower_bracket, &after.lower_bracket, &mut changes);
+    if before.grand_final != after.grand_final {
+        changes.push(String::from("grand final"));
+    }
+    if before.grand_final_reset != after.grand_final_reset {
+        changes.push(String::from("grand final reset"));
+    }
+
+    if before.stages.len() != after.stages.len() {
+        changes.push(String::from("stages"));
+    } else if let Some(stage) = after.stages.iter()
+        .zip(&before.stages)
+        .find(|(after, before)| after != before)
+        .map(|(stage, _)| stage)
+    {
+        changes.push(format!("stage {}", stage.name));
+    }
+
+    changes
+}
+
+fn describe_bracket(
+    name: &str,
+    before: &[Vec<Option<Matchup>>],
+    after: &[Vec<Option<Matchup>>],
+    changes: &mut Vec<String>,
+) {
+    if before.len() != after.len() {
+        changes.push(format!("{} stages", name));
+        return;
+    }
+    for (i, (before_stage, after_stage)) in before.iter().zip(after).enumerate() {
+        let changed = before_stage.iter()
+            .zip(after_stage)
+            .position(|(before, after)| before != after);
+        if let Some(j) = changed {
+            changes.push(format!("{} stage {} matchup {}", name, i + 1, j + 1));
+        }
+    }
+}
+
+fn describe_resources(before: &Resources, after: &Resources) -> Vec<String> {
+    changed_fields(&[
+        ("images", before.images != after.images),
+        ("strings", before.strings != after.strings),
+    ]).into_iter().map(String::from).collect()
+}
+
+fn describe_match(before: &Match, after: &Match) -> Vec<String> {
+    let mut changes: Vec<String> = changed_fields(&[
+        ("team 1", before.team1 != after.team1),
+        ("team 2", before.team2 != after.team2),
+        ("scoreboard swap", before.swap_scoreboard != after.swap_scoreboard),
+        ("linked matchup", before.matchup != after.matchup),
+    ]).into_iter().map(String::from).collect();
+
+    if before.rounds.len() != after.rounds.len() {
+        changes.push(String::from("round count"));
+    }
+    for (i, (before, after)) in before.rounds.iter().zip(&after.rounds).enumerate() {
+        let fields = changed_fields(&[
+            ("gamemode", before.gamemode != after.gamemode),
+            ("map", before.map != after.map),
+            ("score", before.team1_score != after.team1_score || before.team2_score != after.team2_score),
+            ("completion", before.completed != after.completed),
+        ]);
+        if !fields.is_empty() {
+            changes.push(format!("round {} {}", i + 1, fields.join(" and ")));
+        }
+    }
+
+    changes
+}
+
+fn describe_change(before: &Snapshot, after: &Snapshot) -> String {
+    let (section, changes) = match (before, after) {
+        (Snapshot::Settings(before), Snapshot::Settings(after)) =>
+            ("settings", describe_settings(before, after)),
+        (Snapshot::Division(before), Snapshot::Division(after)) =>
+            ("division", describe_division(before, after)),
+        (Snapshot::Resources(before), Snapshot::Resources(after)) =>
+            ("resources", describe_resources(before, after)),
+        (Snapshot::CurrentMatch(before), Snapshot::CurrentMatch(after)) =>
+            ("current match", describe_match(before, after)),
+        (Snapshot::Sections(before), Snapshot::Sections(after)) => {
+            let changes = before.iter()
+                .zip(after)
+                .map(|(before, after)| describe_change(before, after))
+                .map(|description| description.trim_start_matches("Changed ").to_string())
+                .collect();
+            ("project", changes)
+        },
+        _ => ("project", Vec::new()),
+    };
+
+    if changes.is_empty() {
+        format!("Changed {}", section)
+    } else {
+        format!("Changed {}", changes.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renames the event as an edit from `origin`, recording it in `history`.
+    fn rename(history: &mut History, data: &mut SaveData, name: &str, origin: UpdateOrigin) {
+        let before = Snapshot::Settings(data.settings.clone());
+        data.settings.event_name = name.to_string();
+        history.record(before, Snapshot::Settings(data.settings.clone()), origin);
+    }
+
+    #[test]
+    fn quick_edits_to_the_same_thing_are_merged() {
+        let mut history = History::default();
+        let mut data = SaveData::default();
+        let original = data.settings.event_name.clone();
+        for name in ["C", "Cu", "Cup"] {
+            rename(&mut history, &mut data, name, UpdateOrigin::Desktop);
+        }
+        assert_eq!(history.summary().undo.len(), 1);
+
+        // Edits from elsewhere, or once the window has passed, stand on their own
+        rename(&mut history, &mut data, "Cup 2", UpdateOrigin::Remote);
+        assert_eq!(history.summary().undo.len(), 2);
+        rename(&mut history, &mut data, "Cup 3", UpdateOrigin::Desktop);
+        history.undo_stack.back_mut().unwrap().time -= MERGE_WINDOW;
+        rename(&mut history, &mut data, "Cup 4", UpdateOrigin::Desktop);
+        assert_eq!(history.summary().undo.len(), 4);
+
+        while history.undo(&mut data).is_some() {}
+        assert_eq!(data.settings.event_name, original);
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut history = History::default();
+        let mut data = SaveData::default();
+        for i in 0..MAX_HISTORY + 10 {
+            rename(&mut history, &mut data, &i.to_string(), UpdateOrigin::Remote);
+        }
+        assert_eq!(history.summary().undo.len(), MAX_HISTORY);
+
+        // The oldest changes are the ones dropped
+        while history.undo(&mut data).is_some() {}
+        assert_eq!(data.settings.event_name, "9");
+    }
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let mut history = History::default();
+        let mut data = SaveData::default();
+        let original = data.clone();
+        rename(&mut history, &mut data, "Cup", UpdateOrigin::Remote);
+        let before = Snapshot::take(&data, DataSection::All).unwrap();
+        data.division.teams.push(Default::default());
+        data.current_match.swap_scoreboard = true;
+        let after = Snapshot::take(&data, DataSection::All).unwrap();
+        let (before, after) = Snapshot::diff(before, after).unwrap();
+        history.record(before, after, UpdateOrigin::Remote);
+        let edited = data.clone();
+
+        let change = history.undo(&mut data).unwrap();
+        assert_eq!(change.section, DataSection::All);
+        assert_eq!(change.description, "Changed teams, scoreboard swap");
+        assert_eq!(data.division, original.division);
+        assert_eq!(data.current_match, original.current_match);
+        history.undo(&mut data).unwrap();
+        assert_eq!(data, original);
+        assert!(history.undo(&mut data).is_none());
+
+        history.redo(&mut data).unwrap();
+        history.redo(&mut data).unwrap();
+        assert_eq!(data, edited);
+        assert!(history.redo(&mut data).is_none());
+    }
+
+    #[test]
+    fn new_changes_clear_redo() {
+        let mut history = History::default();
+        let mut data = SaveData::default();
+        rename(&mut history, &mut data, "Cup", UpdateOrigin::Remote);
+        history.undo(&mut data).unwrap();
+        assert_eq!(history.summary().redo.len(), 1);
+
+        rename(&mut history, &mut data, "Other Cup", UpdateOrigin::Remote);
+        assert!(history.summary().redo.is_empty());
+        assert!(history.redo(&mut data).is_none());
+        assert_eq!(data.settings.event_name, "Other Cup");
+    }
+
+    #[test]
+    fn redone_changes_are_not_merged_into() {
+        let mut history = History::default();
+        let mut data = SaveData::default();
+        rename(&mut history, &mut data, "Cup", UpdateOrigin::Desktop);
+        history.undo(&mut data).unwrap();
+        history.redo(&mut data).unwrap();
+        rename(&mut history, &mut data, "Cups", UpdateOrigin::Desktop);
+        assert_eq!(history.summary().undo.len(), 2);
+    }
+}
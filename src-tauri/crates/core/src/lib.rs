@@ -0,0 +1,192 @@
+pub mod error;
+pub mod backups;
+pub mod bracket;
+pub mod stages;
+pub mod schedule;
+pub mod models;
+pub mod fs;
+pub mod migrations;
+pub mod updates;
+pub mod validation;
+pub mod autosave;
+pub mod history;
+pub mod config_watcher;
+pub mod context;
+pub mod templates;
+
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, watch};
+
+use models::SaveData;
+use config_watcher::{ConfigWatcher, ExternalChange};
+use history::{ChangeSummary, History, Snapshot};
+use updates::{DataSection, DirtyState, StateUpdate, UpdateOrigin};
+
+/// A loaded project and everything that tracks changes to it. Whatever is
+/// serving or editing the project (the desktop app, the webserver, a script)
+/// shares one of these.
+#[derive(Debug)]
+pub struct ProjectState {
+    pub loaded_config: Option<std::path::PathBuf>,
+    pub original_config: Option<fs::OriginalConfig>,
+    pub data: SaveData,
+    pub revision: u64,
+    pub data_revision: u64,
+    pub saved_revision: Option<u64>,
+    /// The data as it was last loaded or saved, to merge outside edits against
+    pub saved_data: Option<SaveData>,
+    pub saved_contents_hash: Option<u64>,
+    pub template_error: Option<String>,
+    pub template_error_tx: watch::Sender<Option<String>>,
+    pub update_tx: broadcast::Sender<StateUpdate>,
+    pub dirty_tx: watch::Sender<DirtyState>,
+    pub history: History,
+    pub config_watcher: Option<ConfigWatcher>,
+    pub external_change: Option<ExternalChange>,
+    pub external_change_tx: watch::Sender<Option<ExternalChange>>,
+}
+
+pub type SharedState = Arc<Mutex<ProjectState>>;
+
+impl ProjectState {
+    pub fn new(loaded_config: Option<std::path::PathBuf>, data: SaveData) -> Self {
+        Self {
+            loaded_config,
+            original_config: None,
+            data,
+            revision: 0,
+            data_revision: 0,
+            saved_revision: Some(0),
+            saved_data: None,
+            saved_contents_hash: None,
+            template_error: None,
+            template_error_tx: watch::channel(None).0,
+            update_tx: updates::create_update_channel(),
+            dirty_tx: updates::create_dirty_channel(),
+            history: History::default(),
+            config_watcher: None,
+            external_change: None,
+            external_change_tx: watch::channel(None).0,
+        }
+    }
+
+    pub fn new_shared(loaded_config: Option<std::path::PathBuf>, data: SaveData) -> SharedState {
+        Arc::new(Mutex::new(ProjectState::new(loaded_config, data)))
+    }
+
+    pub fn get_base_path(&self) -> Option<std::path::PathBuf> {
+        self.loaded_config.as_ref()
+            .map(|path| fs::remove_file_from_path(path))
+    }
+
+    /// Bumps the revision and notifies any connected overlays that `section` changed.
+    pub fn mark_updated(&mut self, section: DataSection) {
+        self.mark_updated_from(section, UpdateOrigin::Desktop);
+    }
+
+    pub fn mark_updated_from(&mut self, section: DataSection, origin: UpdateOrigin) {
+        self.revision += 1;
+        // An error only means nobody is listening right now
+        let _ = self.update_tx.send(StateUpdate::new(section, self.revision, origin));
+
+        if section != DataSection::Templates {
+            self.data_revision += 1;
+            self.dirty_tx.send_replace(self.dirty_state());
+        }
+    }
+
+    /// Changes part of the data, recording it so it can be undone. Nothing
+    /// is recorded or sent out if the data ends up the same.
+    pub fn update_section<T>(
+        &mut self,
+        section: DataSection,
+        origin: UpdateOrigin,
+        update: impl FnOnce(&mut SaveData) -> T,
+    ) -> T {
+        let before = Snapshot::take(&self.data, section);
+        let result = update(&mut self.data);
+        let after = Snapshot::take(&self.data, section);
+        if let Some((before, after)) = before.zip(after).and_then(|(before, after)| Snapshot::diff(before, after)) {
+            // Only what actually changed needs to be sent out again
+            let section = after.section();
+            self.history.record(before, after, origin);
+            self.mark_updated_from(section, origin);
+        }
+        result
+    }
+
+    /// Changes the current match and brings the matchup it's linked to up to
+    /// date along with it, as one change so undoing it puts both back. Also
+    /// returns whether the matchup changed.
+    pub fn update_current_match<T>(
+        &mut self,
+        origin: UpdateOrigin,
+        update: impl FnOnce(&mut SaveData) -> T,
+    ) -> (T, bool) {
+        self.update_section(DataSection::All, origin, |data| {
+            let before = data.current_match.clone();
+            let result = update(data);
+            let synced = data.sync_linked_matchup(&before);
+            (result, synced)
+        })
+    }
+
+    pub fn undo(&mut self) -> Option<ChangeSummary> {
+        let change = self.history.undo(&mut self.data)?;
+        self.mark_updated(change.section);
+        Some(change)
+    }
+
+    pub fn redo(&mut self) -> Option<ChangeSummary> {
+        let change = self.history.redo(&mut self.data)?;
+        self.mark_updated(change.section);
+        Some(change)
+    }
+
+    /// Records that the data now matches the loaded config on disk.
+    pub fn mark_saved(&mut self) {
+        self.saved_revision = Some(self.data_revision);
+        self.saved_data = Some(self.data.clone());
+        self.dirty_tx.send_replace(self.dirty_state());
+        self.set_external_change(None);
+    }
+
+    /// Records that the file on disk now holds `disk_data`, which the data
+    /// doesn't match, so it needs saving even without any further changes.
+    pub fn mark_out_of_sync(&mut self, disk_data: SaveData) {
+        self.saved_revision = None;
+        self.saved_data = Some(disk_data);
+        self.dirty_tx.send_replace(self.dirty_state());
+        self.set_external_change(None);
+    }
+
+    pub fn set_external_change(&mut self, change: Option<ExternalChange>) {
+        self.external_change = change.clone();
+        self.external_change_tx.send_replace(change);
+    }
+
+    /// Records why the templates last failed to parse, if they did, and
+    /// lets anyone watching know when that changes.
+    pub fn set_template_error(&mut self, error: Option<String>) {
+        self.template_error = error.clone();
+        self.template_error_tx.send_if_modified(|current| {
+            let changed = *current != error;
+            *current = error;
+            changed
+        });
+    }
+
+    pub fn dirty_state(&self) -> DirtyState {
+        DirtyState::new(self.data_revision, self.saved_revision)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_state().dirty
+    }
+}
+
+impl Default for ProjectState {
+    fn default() -> Self {
+        Self::new(None, SaveData::default())
+    }
+}
@@ -0,0 +1,267 @@
+use serde_json::Value;
+
+use crate::models::{new_id, SAVE_DATA_VERSION};
+
+/// Each migration upgrades a file from the version at its index to the next
+/// one. Files from before versioning count as version 0.
+const MIGRATIONS: [fn(&mut Value); SAVE_DATA_VERSION as usize] = [
+    migrate_index_references,
+];
+
+pub fn file_version(data: &Value) -> u32 {
+    data.get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| version as u32)
+}
+
+/// Upgrades a saved file to the current version one step at a time,
+/// returning the version it started at.
+pub fn migrate(data: &mut Value) -> Result<u32, String> {
+    let version = file_version(data);
+    if version > SAVE_DATA_VERSION {
+        return Err(format!(
+            "File was saved by a newer version of the app (version {}, this one supports up to {})",
+            version,
+            SAVE_DATA_VERSION,
+        ));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(data);
+    }
+    if let Some(data) = data.as_object_mut() {
+        data.insert(String::from("version"), Value::from(SAVE_DATA_VERSION));
+    }
+    Ok(version)
+}
+
+/// Gives every object in the list an `id` if it doesn't have one already,
+/// returning all of the list's IDs in order.
+fn assign_ids(list: Option<&mut Value>) -> Vec<Value> {
+    let Some(Value::Array(list)) = list else {
+        return Vec::new();
+    };
+    list.iter_mut()
+        .map(|item| match item {
+            Value::Object(item) => item.entry("id")
+                .or_insert_with(|| Value::String(new_id()))
+                .clone(),
+            _ => Value::Null,
+        })
+        .collect()
+}
+
+/// Replaces an index reference with the ID at that index, or clears it if
+/// it points at nothing. Anything that isn't an index is left alone.
+fn resolve_reference(reference: Option<&mut Value>, ids: &[Value]) {
+    let Some(reference) = reference else {
+        return;
+    };
+    if let Some(index) = reference.as_u64() {
+        *reference = ids.get(index as usize).cloned().unwrap_or(Value::Null);
+    }
+}
+
+fn array_items(value: Option<&mut Value>) -> impl Iterator<Item = &mut Value> {
+    value.and_then(Value::as_array_mut)
+        .into_iter()
+        .flat_map(|items| items.iter_mut())
+}
+
+/// Older config files referred to teams, gamemodes, maps, roles and
+/// characters by their index, which broke whenever a list was reordered.
+/// This gives everything an ID and rewrites those indices into IDs, leaving
+/// already converted files untouched.
+pub fn migrate_index_references(data: &mut Value) {
+    if !data.is_object() {
+        return;
+    }
+    // Stands in for missing sections without adding them to the file
+    let mut missing = Value::Null;
+
+    let settings = data.get_mut("settings").unwrap_or(&mut missing);
+    let gamemode_ids = assign_ids(settings.get_mut("gamemodes"));
+    let map_ids: Vec<Vec<Value>> = array_items(settings.get_mut("gamemodes"))
+        .map(|gamemode| assign_ids(gamemode.get_mut("maps")))
+        .collect();
+    let role_ids = assign_ids(settings.get_mut("roles"));
+    let character_ids = assign_ids(settings.get_mut("characters"));
+
+    let division = data.get_mut("division").unwrap_or(&mut missing);
+    let team_ids = assign_ids(division.get_mut("teams"));
+    for team in array_items(division.get_mut("teams")) {
+        assign_ids(team.get_mut("players"));
+        for player in array_items(team.get_mut("players")) {
+            resolve_reference(player.get_mut("role"), &role_ids);
+            resolve_reference(player.get_mut("character"), &character_ids);
+        }
+    }
+    for stage in array_items(division.get_mut("bracket")) {
+        for matchup in array_items(Some(stage)).filter(|matchup| matchup.is_object()) {
+            matchup.as_object_mut().unwrap()
+                .entry("id")
+                .or_insert_with(|| Value::String(new_id()));
+            resolve_reference(matchup.get_mut("team1"), &team_ids);
+            resolve_reference(matchup.get_mut("team2"), &team_ids);
+        }
+    }
+
+    let current_match = data.get_mut("current_match").unwrap_or(&mut missing);
+    resolve_reference(current_match.get_mut("team1"), &team_ids);
+    resolve_reference(current_match.get_mut("team2"), &team_ids);
+    for round in array_items(current_match.get_mut("rounds")) {
+        // Maps were indexed within their gamemode, so use the old index to find it
+        let gamemode_index = round.get("gamemode")
+            .and_then(Value::as_u64)
+            .map(|index| index as usize);
+        let empty = Vec::new();
+        let round_map_ids = gamemode_index
+            .and_then(|index| map_ids.get(index))
+            .unwrap_or(&empty);
+        resolve_reference(round.get_mut("map"), round_map_ids);
+        resolve_reference(round.get_mut("gamemode"), &gamemode_ids);
+    }
+}
+
+/// The fields that held list indices before `migrate_index_references`.
+const INDEX_REFERENCE_FIELDS: [&str; 6] = ["team1", "team2", "gamemode", "map", "role", "character"];
+
+/// Finds lookups like `teams[matchup.team1]` in a template, which only
+/// worked while references were indices and now need the `*_by_id` maps
+/// instead. Returns the line number (from 1) and the lookup itself.
+pub fn find_index_lookups(template: &str) -> Vec<(usize, String)> {
+    let mut lookups = Vec::new();
+    for (i, line) in template.lines().enumerate() {
+        let mut rest = line;
+        while let Some(open) = rest.find('[') {
+            let inside = &rest[open + 1..];
+            let Some(close) = inside.find(']') else {
+                break;
+            };
+            let list = rest[..open]
+                .rsplit(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches('.');
+            let key = inside[..close].trim();
+            let field = key.rsplit('.').next().unwrap_or(key);
+            if !list.is_empty() && !list.ends_with("_by_id") && INDEX_REFERENCE_FIELDS.contains(&field) {
+                lookups.push((i + 1, format!("{}[{}]", list, key)));
+            }
+            rest = &inside[close + 1..];
+        }
+    }
+    lookups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A file from before versioning, with references by index.
+    fn version_0_file() -> Value {
+        json!({
+            "settings": {
+                "gamemodes": [
+                    { "name": "Control", "maps": [{ "name": "Busan" }, { "name": "Ilios" }] },
+                    { "name": "Escort", "maps": [{ "name": "Dorado" }] },
+                ],
+                "roles": [{ "name": "Tank" }, { "name": "Support" }],
+                "characters": [{ "name": "Ana" }],
+            },
+            "division": {
+                "teams": [
+                    { "name": "Alpha", "players": [{ "name": "One", "role": 1, "character": 0 }] },
+                    { "name": "Bravo", "players": [{ "name": "Two", "role": 7, "character": null }] },
+                ],
+                "bracket": [[{ "team1": 1, "team2": 0 }, null], [{ "team1": 5, "team2": null }]],
+            },
+            "current_match": {
+                "team1": 0,
+                "team2": 1,
+                "rounds": [{ "gamemode": 0, "map": 1 }, { "gamemode": 1, "map": 0 }, { "gamemode": 1, "map": 3 }],
+            },
+        })
+    }
+
+    #[test]
+    fn index_references_become_ids() {
+        let mut data = version_0_file();
+        migrate_index_references(&mut data);
+        let id = |value: &Value| value["id"].clone();
+        let settings = &data["settings"];
+        let teams = &data["division"]["teams"];
+
+        assert!(teams[0]["id"].is_string() && teams[0]["players"][0]["id"].is_string());
+        assert_eq!(data["division"]["bracket"][0][0]["team1"], id(&teams[1]));
+        assert_eq!(data["division"]["bracket"][0][0]["team2"], id(&teams[0]));
+        assert_eq!(data["current_match"]["team1"], id(&teams[0]));
+        assert_eq!(data["current_match"]["team2"], id(&teams[1]));
+        assert_eq!(teams[0]["players"][0]["role"], id(&settings["roles"][1]));
+        assert_eq!(teams[0]["players"][0]["character"], id(&settings["characters"][0]));
+
+        // Maps were counted within their own gamemode
+        let rounds = &data["current_match"]["rounds"];
+        assert_eq!(rounds[0]["gamemode"], id(&settings["gamemodes"][0]));
+        assert_eq!(rounds[0]["map"], id(&settings["gamemodes"][0]["maps"][1]));
+        assert_eq!(rounds[1]["gamemode"], id(&settings["gamemodes"][1]));
+        assert_eq!(rounds[1]["map"], id(&settings["gamemodes"][1]["maps"][0]));
+    }
+
+    #[test]
+    fn out_of_range_indices_are_cleared() {
+        let mut data = version_0_file();
+        migrate_index_references(&mut data);
+        assert_eq!(data["division"]["bracket"][1][0]["team1"], Value::Null);
+        assert_eq!(data["division"]["teams"][1]["players"][0]["role"], Value::Null);
+        assert_eq!(data["current_match"]["rounds"][2]["map"], Value::Null);
+        // An empty slot stays empty rather than becoming a matchup
+        assert_eq!(data["division"]["bracket"][0][1], Value::Null);
+    }
+
+    #[test]
+    fn migrated_files_are_left_alone() {
+        let mut data = version_0_file();
+        migrate_index_references(&mut data);
+        let migrated = data.clone();
+        migrate_index_references(&mut data);
+        assert_eq!(data, migrated);
+    }
+
+    #[test]
+    fn migrate_sets_the_current_version() {
+        let mut data = version_0_file();
+        assert_eq!(migrate(&mut data), Ok(0));
+        assert_eq!(file_version(&data), SAVE_DATA_VERSION);
+
+        let mut newer = json!({ "version": SAVE_DATA_VERSION + 1 });
+        assert!(migrate(&mut newer).is_err());
+    }
+
+    #[test]
+    fn template_index_lookups_are_found() {
+        let template = "\
+<h1>{{ teams[current_match.team1].name }} vs {{ teams[current_match.team2].name }}</h1>
+{% for round in current_match.rounds %}
+{% set map = settings.gamemodes[round.gamemode].maps[round.map] %}
+{% endfor %}";
+        assert_eq!(find_index_lookups(template), vec![
+            (1, String::from("teams[current_match.team1]")),
+            (1, String::from("teams[current_match.team2]")),
+            (3, String::from("settings.gamemodes[round.gamemode]")),
+            (3, String::from("maps[round.map]")),
+        ]);
+    }
+
+    #[test]
+    fn id_and_position_lookups_are_left_alone() {
+        // A position in the list still works, and the *_by_id maps are the fix
+        let template = "\
+{{ teams[0].name }} {{ bracket[1][0].team1 }}
+{% set team = teams_by_id[matchup.team1] %}
+{{ maps_by_id[round.map].name }}";
+        assert!(find_index_lookups(template).is_empty());
+        assert!(find_index_lookups("<p>No lookups here</p>").is_empty());
+    }
+}
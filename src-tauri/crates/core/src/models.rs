@@ -0,0 +1,859 @@
+use serde::{Deserialize, Serialize};
+use std::net::{SocketAddr, ToSocketAddrs};
+use uuid::Uuid;
+
+use crate::{
+    bracket,
+    error::{CommandError, CommandResult},
+};
+
+/// A stable identifier for anything that can be referenced from elsewhere,
+/// so references survive reordering and deleting.
+pub type Id = String;
+
+pub fn new_id() -> Id {
+    Uuid::new_v4().to_string()
+}
+
+/// Bumped whenever the format of saved files changes in a way that needs
+/// a migration (see `migrations`).
+pub const SAVE_DATA_VERSION: u32 = 1;
+
+/// The shortest time autosaving can wait after a change, in seconds, so it
+/// isn't saving on every keystroke.
+pub const MIN_AUTOSAVE_INTERVAL: u64 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SaveData {
+    pub version: u32,
+    pub settings: Settings,
+    pub division: Division,
+    pub resources: Resources,
+    pub current_match: Match,
+}
+
+impl SaveData {
+    pub fn new(settings: Settings, division: Division, resources: Resources, current_match: Match) -> Self {
+        Self {
+            version: SAVE_DATA_VERSION,
+            settings,
+            division,
+            resources,
+            current_match,
+        }
+    }
+
+    pub fn team_names(&self) -> Vec<String> {
+        self.division.teams.iter().map(|team| team.name.clone()).collect()
+    }
+
+    pub fn images_hashmap(&self) -> std::collections::HashMap<String, String> {
+        self.resources.images.iter().map(|asset| (asset.name.clone(), asset.value.clone())).collect()
+    }
+
+    pub fn strings_hashmap(&self) -> std::collections::HashMap<String, String> {
+        self.resources.strings.iter().map(|asset| (asset.name.clone(), asset.value.clone())).collect()
+    }
+
+    pub fn correct_rounds_to_count(&mut self) {
+        while self.settings.round_count < self.current_match.rounds.len() {
+            self.current_match.rounds.pop();
+        }
+        while self.settings.round_count > self.current_match.rounds.len() {
+            self.current_match.rounds.push(Round::default());
+        }
+    }
+
+    /// Sizes the bracket to `bracket_stage_count`, keeping every matchup
+    /// that still fits where it was. Stages are added or removed after the
+    /// last one, so a smaller bracket is the top part of the bigger one and
+    /// first stage results stay in the first stage.
+    pub fn correct_bracket_to_count(&mut self) {
+        let stage_count = self.settings.bracket_stage_count;
+        resize_stages(&mut self.division.bracket, &bracket::upper_bracket_sizes(stage_count));
+
+        // Left alone otherwise, so switching formats back and forth doesn't lose anything
+        if self.settings.bracket_format.is_double_elimination() {
+            resize_stages(&mut self.division.lower_bracket, &bracket::lower_bracket_sizes(stage_count));
+        }
+    }
+
+    /// Replaces the settings, resizing the bracket (see `resize_bracket`) and
+    /// the current match's rounds to fit any new counts along with them.
+    pub fn replace_settings(&mut self, settings: Settings) -> CommandResult<()> {
+        settings.validate().map_err(CommandError::validation)?;
+        let stage_count = settings.bracket_stage_count;
+        self.settings = Settings {
+            bracket_stage_count: self.settings.bracket_stage_count,
+            ..settings
+        };
+        self.resize_bracket(stage_count)?;
+        self.correct_rounds_to_count();
+        Ok(())
+    }
+
+    /// Moves the teams of every decided matchup on to where they play next,
+    /// rebuilding the rest of the bracket from the results (see `bracket::advance_teams`).
+    pub fn advance_bracket(&mut self) {
+        bracket::advance_teams(&self.settings.bracket_format, &mut self.division);
+    }
+
+    /// Moves teams on from the matchups whose result changed since `before`.
+    pub fn advance_bracket_from(&mut self, before: &Division) {
+        bracket::advance_changed_teams(&self.settings.bracket_format, before, &mut self.division);
+    }
+}
+
+fn resize_stages(stages: &mut Vec<Vec<Option<Matchup>>>, sizes: &[usize]) {
+    stages.resize(sizes.len(), Vec::new());
+    for (stage, &size) in stages.iter_mut().zip(sizes) {
+        stage.resize(size, None);
+    }
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self::new(
+            Settings::default(),
+            Division::default(),
+            Resources::default(),
+            Match::default(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Settings {
+    pub event_name: String,
+    pub round_count: usize,
+    pub bracket_stage_count: usize,
+    pub gamemodes: Vec<Gamemode>,
+    pub roles: Vec<Role>,
+    pub characters: Vec<Character>,
+    /// Required to make changes through the webserver; the write API is disabled without one
+    pub api_token: Option<String>,
+    pub webserver: WebserverSettings,
+    pub match_format: MatchFormat,
+    pub bracket_format: BracketFormat,
+    /// Seconds to wait after a change before saving it automatically, or never if unset.
+    /// New projects autosave, but files from before autosaving only do once it's turned on.
+    #[serde(default)]
+    pub autosave_interval: Option<u64>,
+}
+
+impl Settings {
+    /// Everything else starts out at its default, to be filled in with
+    /// struct update syntax where needed.
+    pub fn new(
+        event_name: &str,
+        round_count: usize,
+        bracket_stage_count: usize,
+        gamemodes: Vec<Gamemode>,
+        roles: Vec<Role>,
+        characters: Vec<Character>,
+    ) -> Self {
+        Self {
+            event_name: event_name.to_string(),
+            round_count,
+            bracket_stage_count,
+            gamemodes,
+            roles,
+            characters,
+            api_token: None,
+            webserver: WebserverSettings::default(),
+            match_format: MatchFormat::default(),
+            bracket_format: BracketFormat::default(),
+            autosave_interval: Some(60),
+        }
+    }
+
+    /// Catches settings that can't work before they're applied.
+    pub fn validate(&self) -> Result<(), String> {
+        if let MatchFormat::FirstTo { wins: 0 } = self.match_format {
+            return Err(String::from("A first to match needs at least one win"));
+        }
+        if self.autosave_interval.is_some_and(|interval| interval < MIN_AUTOSAVE_INTERVAL) {
+            return Err(format!("Autosaving has to wait at least {} seconds", MIN_AUTOSAVE_INTERVAL));
+        }
+        bracket::check_stage_count(self.bracket_stage_count)
+    }
+
+    pub fn gamemode(&self, id: &str) -> Option<&Gamemode> {
+        self.gamemodes.iter().find(|gamemode| gamemode.id == id)
+    }
+
+    pub fn role(&self, id: &str) -> Option<&Role> {
+        self.roles.iter().find(|role| role.id == id)
+    }
+
+    pub fn character(&self, id: &str) -> Option<&Character> {
+        self.characters.iter().find(|character| character.id == id)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new("New Event", 5, 3, Vec::new(), Vec::new(), Vec::new())
+    }
+}
+
+/// How the rounds of a match decide its winner.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MatchFormat {
+    /// Whoever wins the majority of the match's rounds
+    #[default]
+    BestOf,
+    /// Whoever wins `wins` rounds first, however many rounds that takes
+    FirstTo { wins: usize },
+    /// Every round is played, so the match can end in a draw
+    FixedRounds,
+}
+
+/// How teams make their way through `Division::bracket` and, for double
+/// elimination, the lower bracket and grand final.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BracketFormat {
+    /// Teams are out after their first loss
+    #[default]
+    SingleElimination,
+    /// Teams drop into the lower bracket after their first loss and are out
+    /// after their second. If `bracket_reset` is set and the lower bracket's
+    /// team wins the grand final, it's played again since the upper
+    /// bracket's team hadn't lost yet.
+    DoubleElimination { bracket_reset: bool },
+}
+
+impl BracketFormat {
+    pub fn is_double_elimination(&self) -> bool {
+        matches!(self, BracketFormat::DoubleElimination { .. })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct WebserverSettings {
+    pub host: String,
+    /// 0 lets the OS pick a free port
+    pub port: u16,
+}
+
+impl WebserverSettings {
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: host.to_string(),
+            port,
+        }
+    }
+
+    pub fn socket_addr(&self) -> Result<SocketAddr, String> {
+        let host = self.host.trim();
+        if host.is_empty() {
+            return Err(String::from("Webserver host cannot be empty"));
+        }
+        (host, self.port).to_socket_addrs()
+            .map_err(|err| format!("Invalid webserver host {:?}: {}", host, err))?
+            .next()
+            .ok_or_else(|| format!("Webserver host {:?} did not resolve to an address", host))
+    }
+}
+
+impl Default for WebserverSettings {
+    fn default() -> Self {
+        Self::new("0.0.0.0", 3000)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Gamemode {
+    #[serde(default = "new_id")]
+    pub id: Id,
+    pub name: String,
+    pub icon: Option<String>,
+    pub maps: Vec<Map>,
+}
+
+impl Gamemode {
+    pub fn new(name: &str, icon: Option<String>, maps: Vec<Map>) -> Self {
+        Self {
+            id: new_id(),
+            name: name.to_string(),
+            icon,
+            maps,
+        }
+    }
+}
+
+impl Gamemode {
+    pub fn map(&self, id: &str) -> Option<&Map> {
+        self.maps.iter().find(|map| map.id == id)
+    }
+}
+
+impl Default for Gamemode {
+    fn default() -> Self {
+        Self::new("New Gamemode", None, Vec::new())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Map {
+    #[serde(default = "new_id")]
+    pub id: Id,
+    pub name: String,
+    pub image: Option<String>,
+}
+
+impl Map {
+    pub fn new(name: &str, image: Option<String>) -> Self {
+        Self {
+            id: new_id(),
+            name: name.to_string(),
+            image,
+        }
+    }
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Self::new("New Map", None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Role {
+    #[serde(default = "new_id")]
+    pub id: Id,
+    pub name: String,
+    pub icon: Option<String>,
+}
+
+impl Role {
+    pub fn new(name: &str, icon: Option<String>) -> Self {
+        Self {
+            id: new_id(),
+            name: name.to_string(),
+            icon,
+        }
+    }
+}
+
+impl Default for Role {
+    fn default() -> Self {
+        Self::new("New Role", None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Character {
+    #[serde(default = "new_id")]
+    pub id: Id,
+    pub name: String,
+    pub image: Option<String>,
+}
+
+impl Character {
+    pub fn new(name: &str, image: Option<String>) -> Self {
+        Self {
+            id: new_id(),
+            name: name.to_string(),
+            image,
+        }
+    }
+}
+
+impl Default for Character {
+    fn default() -> Self {
+        Self::new("New Character", None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Resources {
+    pub images: Vec<ResourcePair>,
+    pub strings: Vec<ResourcePair>,
+}
+
+impl Resources {
+    pub fn new(images: Vec<ResourcePair>, strings: Vec<ResourcePair>) -> Self {
+        Self {
+            images,
+            strings,
+        }
+    }
+}
+
+impl Default for Resources {
+    fn default() -> Self {
+        Self::new(Vec::new(), Vec::new())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct ResourcePair {
+    pub name: String,
+    pub value: String,
+}
+
+impl ResourcePair {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl Default for ResourcePair {
+    fn default() -> Self {
+        Self::new("", "")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Division {
+    pub teams: Vec<Team>,
+    /// Each stage has half as many matchups as the one before it, ending
+    /// with the final. This is the upper bracket in double elimination.
+    pub bracket: Vec<Vec<Option<Matchup>>>,
+    /// Only used in double elimination, sized from the upper bracket by
+    /// `bracket::lower_bracket_sizes`
+    pub lower_bracket: Vec<Vec<Option<Matchup>>>,
+    pub grand_final: Option<Matchup>,
+    pub grand_final_reset: Option<Matchup>,
+    /// Groups and other stages played outside the bracket
+    pub stages: Vec<Stage>,
+}
+
+impl Division {
+    pub fn new(
+        teams: Vec<Team>,
+        bracket: Option<Vec<Vec<Option<Matchup>>>>
+    ) -> Self {
+        let bracket = match bracket {
+            Some(bracket) => bracket,
+            None => vec![
+                vec![None; 4],
+                vec![None; 2],
+                vec![None],
+            ],
+        };
+        Self {
+            teams,
+            bracket,
+            lower_bracket: Vec::new(),
+            grand_final: None,
+            grand_final_reset: None,
+            stages: Vec::new(),
+        }
+    }
+}
+
+impl Division {
+    pub fn team(&self, id: &str) -> Option<&Team> {
+        self.teams.iter().find(|team| team.id == id)
+    }
+}
+
+impl Default for Division {
+    fn default() -> Self {
+        Self::new(Vec::new(), None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Matchup {
+    #[serde(default = "new_id")]
+    pub id: Id,
+    pub team1: Option<Id>,
+    pub team2: Option<Id>,
+    pub team1_score: usize,
+    pub team2_score: usize,
+    pub completed: bool,
+    /// The scores of each map played, if they were recorded. Only used for
+    /// round differentials in standings.
+    pub rounds: Vec<Round>,
+}
+
+impl Matchup {
+    pub fn new(
+        team1: Option<Id>,
+        team2: Option<Id>,
+        team1_score: usize,
+        team2_score: usize,
+        completed: bool,
+    ) -> Self {
+        Self {
+            id: new_id(),
+            team1,
+            team2,
+            team1_score,
+            team2_score,
+            completed,
+            rounds: Vec::new(),
+        }
+    }
+
+    pub fn is_filled(&self) -> bool {
+        self.team1.is_some() && self.team2.is_some()
+    }
+
+    /// The team that won, once the matchup is completed with a higher score.
+    /// A completed matchup with only one team is a bye, which that team wins.
+    pub fn winner(&self) -> Option<&Id> {
+        if !self.completed {
+            return None;
+        }
+        if !self.is_filled() {
+            return self.team1.as_ref().or(self.team2.as_ref());
+        }
+        match self.team1_score.cmp(&self.team2_score) {
+            std::cmp::Ordering::Greater => self.team1.as_ref(),
+            std::cmp::Ordering::Less => self.team2.as_ref(),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    pub fn loser(&self) -> Option<&Id> {
+        if !self.completed || !self.is_filled() {
+            return None;
+        }
+        match self.team1_score.cmp(&self.team2_score) {
+            std::cmp::Ordering::Greater => self.team2.as_ref(),
+            std::cmp::Ordering::Less => self.team1.as_ref(),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+impl Default for Matchup {
+    fn default() -> Self {
+        Self::new(None, None, 0, 0, false)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StageFormat {
+    /// Every team plays every other team once
+    #[default]
+    RoundRobin,
+    /// Teams with similar records are paired one round at a time, for
+    /// `round_count` rounds
+    Swiss { round_count: usize },
+}
+
+/// A group of teams playing each other outside the bracket, ranked by a
+/// standings table instead of eliminated.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Stage {
+    #[serde(default = "new_id")]
+    pub id: Id,
+    pub name: String,
+    pub format: StageFormat,
+    pub teams: Vec<Id>,
+    /// The matchups played in each round of the stage
+    pub rounds: Vec<Vec<Matchup>>,
+}
+
+impl Stage {
+    pub fn new(name: &str, format: StageFormat, teams: Vec<Id>) -> Self {
+        Self {
+            id: new_id(),
+            name: name.to_string(),
+            format,
+            teams,
+            rounds: Vec::new(),
+        }
+    }
+
+    pub fn matchups(&self) -> impl Iterator<Item = &Matchup> {
+        self.rounds.iter().flatten()
+    }
+
+    pub fn matchups_mut(&mut self) -> impl Iterator<Item = &mut Matchup> {
+        self.rounds.iter_mut().flatten()
+    }
+}
+
+impl Default for Stage {
+    fn default() -> Self {
+        Self::new("", StageFormat::default(), Vec::new())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Match {
+    pub rounds: Vec<Round>,
+    pub team1: Option<Id>,
+    pub team2: Option<Id>,
+    pub swap_scoreboard: bool,
+    /// The bracket or stage matchup being played, which is kept up to date
+    /// with the match's result
+    pub matchup: Option<Id>,
+}
+
+impl Match {
+    pub fn new(
+        rounds: Vec<Round>,
+        team1: Option<Id>,
+        team2: Option<Id>,
+        swap_scoreboard: bool,
+    ) -> Self {
+        Self {
+            rounds,
+            team1,
+            team2,
+            swap_scoreboard,
+            matchup: None,
+        }
+    }
+
+    pub(crate) fn completed_rounds(&self) -> impl Iterator<Item = &Round> {
+        self.rounds.iter().filter(|round| round.completed)
+    }
+
+    pub fn team1_score(&self) -> usize {
+        self.completed_rounds().filter(|round| round.team1_score > round.team2_score).count()
+    }
+
+    pub fn team2_score(&self) -> usize {
+        self.completed_rounds().filter(|round| round.team2_score > round.team1_score).count()
+    }
+
+    pub fn result(&self, format: &MatchFormat) -> MatchResult {
+        let team1_score = self.team1_score();
+        let team2_score = self.team2_score();
+        let all_completed = self.rounds.iter().all(|round| round.completed);
+        // Nothing is decided before a round has been played, however few rounds there are
+        let started = self.completed_rounds().next().is_some();
+
+        let decided = started && match format {
+            MatchFormat::BestOf => {
+                let needed = self.rounds.len() / 2 + 1;
+                team1_score >= needed || team2_score >= needed || all_completed
+            },
+            MatchFormat::FirstTo { wins } => {
+                team1_score >= *wins || team2_score >= *wins
+            },
+            MatchFormat::FixedRounds => all_completed,
+        };
+
+        let winner = match team1_score.cmp(&team2_score) {
+            _ if !decided => None,
+            std::cmp::Ordering::Greater => Some(1),
+            std::cmp::Ordering::Less => Some(2),
+            std::cmp::Ordering::Equal => None,
+        };
+
+        // Once the match is decided, nothing left to play can change it
+        let moot_rounds = if decided {
+            self.rounds.iter().enumerate()
+                .filter(|(_, round)| !round.completed)
+                .map(|(i, _)| i)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        MatchResult {
+            team1_score,
+            team2_score,
+            decided,
+            winner,
+            draw: decided && winner.is_none(),
+            moot_rounds,
+        }
+    }
+}
+
+impl Default for Match {
+    fn default() -> Self {
+        Self::new(Vec::new(), None, None, false)
+    }
+}
+
+/// The state of a match as far as its completed rounds and format are concerned.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct MatchResult {
+    pub team1_score: usize,
+    pub team2_score: usize,
+    pub decided: bool,
+    /// 1 or 2, only once the match is decided
+    pub winner: Option<usize>,
+    pub draw: bool,
+    /// Indices of the rounds that no longer need to be played
+    pub moot_rounds: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Round {
+    pub gamemode: Option<Id>,
+    pub map: Option<Id>,
+    pub team1_score: usize,
+    pub team2_score: usize,
+    pub completed: bool,
+}
+
+impl Round {
+    pub fn new(
+        gamemode: Option<Id>,
+        map: Option<Id>,
+        team1_score: usize,
+        team2_score: usize,
+        completed: bool
+    ) -> Self {
+        Self {
+            gamemode,
+            map,
+            team1_score,
+            team2_score,
+            completed,
+        }
+    }
+}
+
+impl Default for Round {
+    fn default() -> Self {
+        Self::new(None, None, 0, 0, false)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Player {
+    #[serde(default = "new_id")]
+    pub id: Id,
+    pub name: String,
+    pub role: Option<Id>,
+    pub character: Option<Id>,
+}
+
+impl Player {
+    pub fn new(name: &str, role: Option<Id>, character: Option<Id>) -> Self {
+        Self {
+            id: new_id(),
+            name: name.to_string(),
+            role,
+            character,
+        }
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new("New Player", None, None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct Team {
+    #[serde(default = "new_id")]
+    pub id: Id,
+    pub name: String,
+    pub icon: Option<String>,
+    pub players: Vec<Player>,
+}
+
+impl Team {
+    pub fn new(name: &str, icon: Option<String>, players: Vec<Player>) -> Self {
+        Self {
+            id: new_id(),
+            name: name.to_string(),
+            icon,
+            players,
+        }
+    }
+}
+
+impl Default for Team {
+    fn default() -> Self {
+        Self::new("New Team", None, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A match with a round for each character of `rounds`: `1` or `2` for a
+    /// round that team won, `=` for a drawn round and `-` for one not played yet.
+    fn match_of(rounds: &str) -> Match {
+        let rounds = rounds.chars()
+            .map(|round| {
+                let (team1_score, team2_score) = match round {
+                    '1' => (1, 0),
+                    '2' => (0, 1),
+                    _ => (0, 0),
+                };
+                Round { team1_score, team2_score, completed: round != '-', ..Round::default() }
+            })
+            .collect();
+        Match { rounds, ..Match::default() }
+    }
+
+    fn outcome(rounds: &str, format: &MatchFormat) -> (bool, Option<usize>, bool) {
+        let result = match_of(rounds).result(format);
+        (result.decided, result.winner, result.draw)
+    }
+
+    #[test]
+    fn best_of_needs_a_majority() {
+        let format = MatchFormat::BestOf;
+        assert_eq!(outcome("-----", &format), (false, None, false));
+        assert_eq!(outcome("12---", &format), (false, None, false));
+        assert_eq!(outcome("112--", &format), (false, None, false));
+        assert_eq!(outcome("1121-", &format), (true, Some(1), false));
+        assert_eq!(outcome("222--", &format), (true, Some(2), false));
+        assert_eq!(match_of("222--").result(&format).moot_rounds, vec![3, 4]);
+        assert_eq!(outcome("12=12=", &format), (true, None, true));
+    }
+
+    #[test]
+    fn first_to_plays_until_someone_gets_there() {
+        let format = MatchFormat::FirstTo { wins: 2 };
+        assert_eq!(outcome("", &format), (false, None, false));
+        assert_eq!(outcome("1=2==-", &format), (false, None, false));
+        assert_eq!(outcome("1==1--", &format), (true, Some(1), false));
+        assert_eq!(outcome("212", &format), (true, Some(2), false));
+    }
+
+    #[test]
+    fn fixed_rounds_are_all_played() {
+        let format = MatchFormat::FixedRounds;
+        assert_eq!(outcome("----", &format), (false, None, false));
+        assert_eq!(outcome("111-", &format), (false, None, false));
+        assert_eq!(outcome("1112", &format), (true, Some(1), false));
+        assert_eq!(outcome("2212", &format), (true, Some(2), false));
+        assert_eq!(outcome("1221", &format), (true, None, true));
+    }
+
+    #[test]
+    fn autosave_interval_has_a_minimum() {
+        let mut settings = Settings::default();
+        assert!(settings.validate().is_ok());
+        settings.autosave_interval = None;
+        assert!(settings.validate().is_ok());
+        settings.autosave_interval = Some(0);
+        assert!(settings.validate().is_err());
+        settings.autosave_interval = Some(MIN_AUTOSAVE_INTERVAL - 1);
+        assert!(settings.validate().is_err());
+        settings.autosave_interval = Some(MIN_AUTOSAVE_INTERVAL);
+        assert!(settings.validate().is_ok());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    bracket::{self, BracketPosition, BracketSide},
+    error::{CommandError, CommandResult},
+    models::{Division, Id, Match, Matchup, Round, SaveData},
+};
+
+/// Roughly when a bracket matchup is played. Each lower bracket stage comes
+/// after the upper bracket stage that drops teams into it.
+fn bracket_order(position: &BracketPosition) -> (usize, usize) {
+    match position.side {
+        BracketSide::Upper => (position.stage * 2, 0),
+        BracketSide::Lower => (position.stage + 1, 1),
+        BracketSide::GrandFinal => (usize::MAX, 0),
+        BracketSide::GrandFinalReset => (usize::MAX, 1),
+    }
+}
+
+impl Division {
+    pub fn matchup(&self, id: &str) -> Option<&Matchup> {
+        self.bracket_matchups()
+            .chain(self.stages.iter().flat_map(|stage| stage.matchups()))
+            .find(|matchup| matchup.id == id)
+    }
+
+    pub fn matchup_mut(&mut self, id: &str) -> Option<&mut Matchup> {
+        let in_stage = self.stages.iter()
+            .any(|stage| stage.matchups().any(|matchup| matchup.id == id));
+        if in_stage {
+            return self.stages.iter_mut()
+                .flat_map(|stage| stage.matchups_mut())
+                .find(|matchup| matchup.id == id);
+        }
+        self.bracket_matchups_mut()
+            .map(|(_, matchup)| matchup)
+            .find(|matchup| matchup.id == id)
+    }
+}
+
+impl SaveData {
+    /// Every matchup in the order it's expected to be played: each round of
+    /// the stages, then the bracket one stage at a time.
+    pub fn matchups_in_play_order(&self) -> Vec<&Matchup> {
+        let round_count = self.division.stages.iter()
+            .map(|stage| stage.rounds.len())
+            .max()
+            .unwrap_or(0);
+        let stage_matchups = (0..round_count).flat_map(|round| self.division.stages.iter()
+            .filter_map(move |stage| stage.rounds.get(round))
+            .flatten());
+
+        let mut positions = bracket::positions(&self.settings.bracket_format, &self.division);
+        positions.sort_by_key(bracket_order);
+        let bracket_matchups = positions.into_iter()
+            .filter_map(|position| self.division.bracket_matchup(position));
+
+        stage_matchups.chain(bracket_matchups).collect()
+    }
+
+    /// Links the current match to a matchup, or unlinks it with `None`.
+    /// The match takes on the matchup's teams and any rounds it has
+    /// recorded, with fresh rounds for the rest.
+    pub fn link_matchup(&mut self, id: Option<Id>) -> CommandResult<()> {
+        let Some(id) = id else {
+            self.current_match.matchup = None;
+            return Ok(());
+        };
+        let matchup = self.division.matchup(&id)
+            .ok_or_else(|| CommandError::validation(format!("No matchup with ID {}", id)))?;
+        let mut rounds = matchup.rounds.clone();
+        if rounds.len() < self.settings.round_count {
+            rounds.resize(self.settings.round_count, Round::default());
+        }
+
+        self.current_match.team1 = matchup.team1.clone();
+        self.current_match.team2 = matchup.team2.clone();
+        self.current_match.rounds = rounds;
+        self.current_match.matchup = Some(id);
+        Ok(())
+    }
+
+    /// Copies what changed in the current match since `before` into the
+    /// matchup it's linked to, moving teams on through the bracket if that
+    /// decides it. Returns whether anything changed.
+    pub fn sync_linked_matchup(&mut self, before: &Match) -> bool {
+        let Some(id) = self.current_match.matchup.clone() else {
+            return false;
+        };
+        // A newly linked match was just set up from the matchup, so there's
+        // nothing to copy back yet
+        if before.matchup.as_ref() != Some(&id) {
+            return false;
+        }
+        let current_match = &self.current_match;
+        let teams_changed = current_match.team1 != before.team1 || current_match.team2 != before.team2;
+        let rounds_changed = current_match.completed_rounds().ne(before.completed_rounds());
+        if !teams_changed && !rounds_changed {
+            return false;
+        }
+        let result = current_match.result(&self.settings.match_format);
+        let rounds: Vec<Round> = current_match.completed_rounds().cloned().collect();
+        let (team1, team2) = (current_match.team1.clone(), current_match.team2.clone());
+
+        let division = self.division.clone();
+        let Some(matchup) = self.division.matchup_mut(&id) else {
+            return false;
+        };
+        let old = matchup.clone();
+        if teams_changed {
+            matchup.team1 = team1;
+            matchup.team2 = team2;
+        }
+        // Clearing the rounds to play again shouldn't undo a result that was
+        // already recorded
+        if rounds_changed && !(matchup.completed && rounds.is_empty()) {
+            matchup.team1_score = result.team1_score;
+            matchup.team2_score = result.team2_score;
+            matchup.completed = result.decided;
+            matchup.rounds = rounds;
+        }
+
+        let changed = *matchup != old;
+        if changed {
+            self.advance_bracket_from(&division);
+        }
+        changed
+    }
+
+    /// Sets up the current match for the first matchup that has both of its
+    /// teams but hasn't been played yet, other than the one already linked.
+    pub fn load_next_matchup(&mut self) -> CommandResult<Id> {
+        let linked = self.current_match.matchup.as_ref();
+        let id = self.matchups_in_play_order().into_iter()
+            .find(|matchup| matchup.is_filled() && !matchup.completed && Some(&matchup.id) != linked)
+            .map(|matchup| matchup.id.clone())
+            .ok_or_else(|| CommandError::validation("There are no more matchups ready to be played"))?;
+
+        self.link_matchup(Some(id.clone()))?;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{Stage, StageFormat},
+        updates::{DataSection, UpdateOrigin},
+    };
+
+    /// A project with a round-robin stage of four teams, with the current
+    /// match linked to its first matchup.
+    fn linked_project() -> SaveData {
+        let mut data = SaveData::default();
+        let teams: Vec<Id> = ["a", "b", "c", "d"].map(String::from).to_vec();
+        let mut stage = Stage::new("Group", StageFormat::RoundRobin, teams);
+        stage.generate_schedule();
+        data.division.stages.push(stage);
+        data.load_next_matchup().unwrap();
+        data
+    }
+
+    fn linked(data: &SaveData) -> &Matchup {
+        data.division.matchup(data.current_match.matchup.as_ref().unwrap()).unwrap()
+    }
+
+    /// Completes a round of the current match and syncs it, returning
+    /// whether the linked matchup changed.
+    fn complete_round(data: &mut SaveData, index: usize, team1_score: usize, team2_score: usize) -> bool {
+        let before = data.current_match.clone();
+        let round = &mut data.current_match.rounds[index];
+        (round.team1_score, round.team2_score, round.completed) = (team1_score, team2_score, true);
+        data.sync_linked_matchup(&before)
+    }
+
+    #[test]
+    fn next_matchup_sets_up_its_teams_and_rounds() {
+        let mut data = linked_project();
+        let first = data.division.stages[0].rounds[0][0].clone();
+        assert_eq!(data.current_match.matchup.as_ref(), Some(&first.id));
+        assert_eq!((&data.current_match.team1, &data.current_match.team2), (&first.team1, &first.team2));
+        assert_eq!(data.current_match.rounds, vec![Round::default(); data.settings.round_count]);
+
+        for index in 0..3 {
+            assert!(complete_round(&mut data, index, 2, 0));
+        }
+        assert!(linked(&data).completed);
+        let second = data.load_next_matchup().unwrap();
+        assert_eq!(second, data.division.stages[0].rounds[0][1].id);
+        assert!(data.current_match.rounds.iter().all(|round| !round.completed));
+    }
+
+    #[test]
+    fn linking_a_matchup_does_not_sync_it() {
+        let mut data = linked_project();
+        assert!(complete_round(&mut data, 0, 2, 0));
+        let first = linked(&data).clone();
+        let other = data.division.stages[0].rounds[1][0].clone();
+
+        let before = data.current_match.clone();
+        data.link_matchup(Some(other.id.clone())).unwrap();
+        assert_eq!((&data.current_match.team1, &data.current_match.team2), (&other.team1, &other.team2));
+        assert!(!data.sync_linked_matchup(&before));
+        assert_eq!(data.division.matchup(&other.id), Some(&other));
+        assert_eq!(data.division.matchup(&first.id), Some(&first));
+
+        // Going back to the first matchup picks up the round it recorded
+        data.link_matchup(Some(first.id.clone())).unwrap();
+        assert_eq!(data.current_match.rounds.len(), data.settings.round_count);
+        assert_eq!(data.current_match.rounds[0], first.rounds[0]);
+
+        assert!(data.link_matchup(Some(String::from("missing"))).is_err());
+        data.link_matchup(None).unwrap();
+        assert_eq!(data.current_match.matchup, None);
+    }
+
+    #[test]
+    fn only_changed_fields_are_synced() {
+        let mut data = linked_project();
+        let id = data.current_match.matchup.clone().unwrap();
+        // Corrected on the stages page while the match is linked
+        data.division.matchup_mut(&id).unwrap().team1 = Some(String::from("d"));
+
+        let before = data.current_match.clone();
+        data.current_match.swap_scoreboard = true;
+        assert!(!data.sync_linked_matchup(&before));
+
+        assert!(complete_round(&mut data, 0, 2, 0));
+        let matchup = linked(&data);
+        assert_eq!(matchup.team1.as_deref(), Some("d"));
+        assert_eq!((matchup.team1_score, matchup.team2_score), (1, 0));
+
+        let before = data.current_match.clone();
+        data.current_match.team1 = Some(String::from("c"));
+        assert!(data.sync_linked_matchup(&before));
+        assert_eq!(linked(&data).team1.as_deref(), Some("c"));
+        assert_eq!(linked(&data).team1_score, 1);
+    }
+
+    #[test]
+    fn clearing_rounds_keeps_a_recorded_result() {
+        let mut data = linked_project();
+        for index in 0..3 {
+            complete_round(&mut data, index, 2, 0);
+        }
+        let played = linked(&data).clone();
+        assert!(played.completed);
+
+        let before = data.current_match.clone();
+        data.current_match.rounds = vec![Round::default(); data.settings.round_count];
+        assert!(!data.sync_linked_matchup(&before));
+        assert_eq!(linked(&data), &played);
+
+        // Playing it again replaces the result
+        assert!(complete_round(&mut data, 0, 0, 2));
+        let matchup = linked(&data);
+        assert!(!matchup.completed);
+        assert_eq!((matchup.team1_score, matchup.team2_score), (0, 1));
+    }
+
+    #[test]
+    fn match_edits_are_undone_with_their_matchup() {
+        let mut state = crate::ProjectState::new(None, linked_project());
+        let (_, synced) = state.update_current_match(UpdateOrigin::Remote, |data| {
+            data.current_match.rounds[0].team1_score = 2;
+            data.current_match.rounds[0].completed = true;
+        });
+        assert!(synced);
+        assert_eq!(linked(&state.data).team1_score, 1);
+
+        let change = state.undo().unwrap();
+        assert_eq!(change.section, DataSection::All);
+        assert!(!state.data.current_match.rounds[0].completed);
+        assert_eq!(linked(&state.data).team1_score, 0);
+        assert!(state.history.summary().undo.is_empty());
+
+        state.redo().unwrap();
+        assert!(state.data.current_match.rounds[0].completed);
+        assert_eq!(linked(&state.data).team1_score, 1);
+    }
+}
@@ -0,0 +1,512 @@
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::{CommandError, CommandResult},
+    models::{Division, Id, Matchup, Stage, StageFormat},
+};
+
+/// Pairs every team with every other team once, using the circle method so
+/// each team plays once per round. With an odd number of teams, one sits out
+/// each round. Teams swap sides from round to round so nobody is always team 1.
+pub fn round_robin_schedule(teams: &[Id]) -> Vec<Vec<Matchup>> {
+    let mut slots: Vec<Option<&Id>> = teams.iter().map(Some).collect();
+    if slots.len() % 2 == 1 {
+        slots.push(None);
+    }
+
+    let slot_count = slots.len();
+    let mut rounds = Vec::new();
+    for round in 0..slot_count.saturating_sub(1) {
+        let matchups = (0..slot_count / 2)
+            .filter_map(|index| {
+                let home = slots[index]?;
+                let away = slots[slot_count - 1 - index]?;
+                let (team1, team2) = if round % 2 == 1 { (away, home) } else { (home, away) };
+                Some(Matchup::new(Some(team1.clone()), Some(team2.clone()), 0, 0, false))
+            })
+            .collect();
+        rounds.push(matchups);
+        // The first team stays put while everyone else moves around it
+        slots[1..].rotate_right(1);
+    }
+    rounds
+}
+
+/// One team's row in a stage's standings table.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Standing {
+    /// Teams that can't be separated share a rank
+    pub rank: usize,
+    pub team: Id,
+    pub played: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub map_wins: usize,
+    pub map_losses: usize,
+    pub map_differential: i64,
+    pub round_wins: usize,
+    pub round_losses: usize,
+    pub round_differential: i64,
+    /// Rounds sat out, each counted as a win
+    pub byes: usize,
+    /// The total wins of every team played against
+    pub buchholz: usize,
+    /// The average win percentage of every team played against, from 0 to 1
+    pub opponent_win_percentage: f64,
+}
+
+impl Standing {
+    fn new(team: &Id) -> Self {
+        Self {
+            rank: 1,
+            team: team.clone(),
+            played: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            map_wins: 0,
+            map_losses: 0,
+            map_differential: 0,
+            round_wins: 0,
+            round_losses: 0,
+            round_differential: 0,
+            byes: 0,
+            buchholz: 0,
+            opponent_win_percentage: 0.0,
+        }
+    }
+
+    fn record_bye(&mut self) {
+        self.played += 1;
+        self.wins += 1;
+        self.byes += 1;
+    }
+
+    /// Draws count as half a win.
+    pub fn win_percentage(&self) -> f64 {
+        if self.played == 0 {
+            return 0.0;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.played as f64
+    }
+
+    fn points(&self) -> usize {
+        self.wins * 2 + self.draws
+    }
+
+    fn record(&mut self, map_wins: usize, map_losses: usize, round_wins: usize, round_losses: usize) {
+        self.played += 1;
+        match map_wins.cmp(&map_losses) {
+            Ordering::Greater => self.wins += 1,
+            Ordering::Less => self.losses += 1,
+            Ordering::Equal => self.draws += 1,
+        }
+        self.map_wins += map_wins;
+        self.map_losses += map_losses;
+        self.map_differential = self.map_wins as i64 - self.map_losses as i64;
+        self.round_wins += round_wins;
+        self.round_losses += round_losses;
+        self.round_differential = self.round_wins as i64 - self.round_losses as i64;
+    }
+
+    /// Better records sort first: most wins, fewest losses, then map and
+    /// round differential. Swiss stages look at the strength of each team's
+    /// opponents before the differentials, since teams don't all play each other.
+    fn compare(&self, other: &Self, format: &StageFormat) -> Ordering {
+        let record = other.wins.cmp(&self.wins)
+            .then(self.losses.cmp(&other.losses));
+        let opponents = match format {
+            StageFormat::RoundRobin => Ordering::Equal,
+            StageFormat::Swiss { .. } => other.buchholz.cmp(&self.buchholz)
+                .then(other.opponent_win_percentage.total_cmp(&self.opponent_win_percentage)),
+        };
+        record
+            .then(opponents)
+            .then(other.map_differential.cmp(&self.map_differential))
+            .then(other.round_differential.cmp(&self.round_differential))
+    }
+}
+
+/// The two teams of a completed matchup that are both part of the stage.
+fn completed_teams<'a>(matchup: &'a Matchup, stage: &Stage) -> Option<(&'a Id, &'a Id)> {
+    let team1 = matchup.team1.as_ref()?;
+    let team2 = matchup.team2.as_ref()?;
+    (matchup.completed && stage.teams.contains(team1) && stage.teams.contains(team2))
+        .then_some((team1, team2))
+}
+
+/// The team given a bye by a completed matchup with nobody else in it.
+fn bye_team<'a>(matchup: &'a Matchup, stage: &Stage) -> Option<&'a Id> {
+    match (&matchup.team1, &matchup.team2) {
+        (Some(team), None) | (None, Some(team)) if matchup.completed && stage.teams.contains(team) => Some(team),
+        _ => None,
+    }
+}
+
+/// How many of the matchups between `teams` each of them won.
+fn head_to_head_wins(stage: &Stage, teams: &[&Id]) -> HashMap<Id, usize> {
+    let mut wins = HashMap::new();
+    for matchup in stage.matchups() {
+        let Some((team1, team2)) = completed_teams(matchup, stage) else {
+            continue;
+        };
+        if !teams.contains(&team1) || !teams.contains(&team2) {
+            continue;
+        }
+        match matchup.team1_score.cmp(&matchup.team2_score) {
+            Ordering::Greater => *wins.entry(team1.clone()).or_insert(0) += 1,
+            Ordering::Less => *wins.entry(team2.clone()).or_insert(0) += 1,
+            Ordering::Equal => {},
+        }
+    }
+    wins
+}
+
+/// Ranks the teams in a stage by their completed matchups. Teams level on
+/// record, opponent strength (in Swiss stages), map differential and round
+/// differential are separated by the matchups they played against each other.
+pub fn standings(stage: &Stage) -> Vec<Standing> {
+    let mut table: Vec<Standing> = stage.teams.iter().map(Standing::new).collect();
+    let mut opponents: HashMap<&Id, Vec<&Id>> = HashMap::new();
+
+    for matchup in stage.matchups() {
+        if let Some(team) = bye_team(matchup, stage) {
+            if let Some(standing) = table.iter_mut().find(|standing| &standing.team == team) {
+                standing.record_bye();
+            }
+            continue;
+        }
+        let Some((team1, team2)) = completed_teams(matchup, stage) else {
+            continue;
+        };
+        opponents.entry(team1).or_default().push(team2);
+        opponents.entry(team2).or_default().push(team1);
+        let team1_rounds: usize = matchup.rounds.iter().map(|round| round.team1_score).sum();
+        let team2_rounds: usize = matchup.rounds.iter().map(|round| round.team2_score).sum();
+        if let Some(standing) = table.iter_mut().find(|standing| &standing.team == team1) {
+            standing.record(matchup.team1_score, matchup.team2_score, team1_rounds, team2_rounds);
+        }
+        if let Some(standing) = table.iter_mut().find(|standing| &standing.team == team2) {
+            standing.record(matchup.team2_score, matchup.team1_score, team2_rounds, team1_rounds);
+        }
+    }
+
+    let records: HashMap<Id, (usize, f64)> = table.iter()
+        .map(|standing| (standing.team.clone(), (standing.wins, standing.win_percentage())))
+        .collect();
+    for standing in table.iter_mut() {
+        let Some(opponents) = opponents.get(&standing.team) else {
+            continue;
+        };
+        standing.buchholz = opponents.iter().map(|opponent| records[*opponent].0).sum();
+        standing.opponent_win_percentage = opponents.iter()
+            .map(|opponent| records[*opponent].1)
+            .sum::<f64>() / opponents.len() as f64;
+    }
+
+    table.sort_by(|a, b| a.compare(b, &stage.format));
+
+    let mut start = 0;
+    while start < table.len() {
+        let end = start + table[start..].iter()
+            .take_while(|standing| standing.compare(&table[start], &stage.format) == Ordering::Equal)
+            .count();
+        let tied: Vec<&Id> = table[start..end].iter().map(|standing| &standing.team).collect();
+        let wins = head_to_head_wins(stage, &tied);
+        let wins_of = |standing: &Standing| wins.get(&standing.team).copied().unwrap_or(0);
+
+        table[start..end].sort_by_key(|standing| std::cmp::Reverse(wins_of(standing)));
+        for index in start..end {
+            table[index].rank = if index > start && wins_of(&table[index]) == wins_of(&table[index - 1]) {
+                table[index - 1].rank
+            } else {
+                index + 1
+            };
+        }
+        start = end;
+    }
+
+    table
+}
+
+/// How many steps the search for a pairing can take before giving up and
+/// allowing another rematch. Proving that teams can't be paired without
+/// rematches can take an exponential number of steps, which happens easily
+/// late in a stage with a lot of teams.
+const MAX_PAIRING_STEPS: usize = 10_000;
+
+/// Pairs off `teams` (best first) with at most `rematches` of the pairs in
+/// `played`, preferring the top half of each group of teams on the same
+/// points to play the bottom half. Teams drop down to the next group if their
+/// own doesn't work out. Gives up once `steps` runs out.
+fn pair_teams<'a>(
+    teams: &[&'a Id],
+    points: &HashMap<&Id, usize>,
+    played: &HashSet<(&Id, &Id)>,
+    rematches: usize,
+    steps: &mut usize,
+) -> Option<Vec<(&'a Id, &'a Id)>> {
+    let Some((&team, rest)) = teams.split_first() else {
+        return Some(Vec::new());
+    };
+    *steps = steps.checked_sub(1)?;
+
+    let group_len = rest.iter()
+        .take_while(|other| points.get(*other) == points.get(team))
+        .count();
+    let candidates = (group_len / 2..group_len)
+        .chain(0..group_len / 2)
+        .chain(group_len..rest.len());
+    for index in candidates {
+        let opponent = rest[index];
+        let is_rematch = played.contains(&(team, opponent));
+        if is_rematch && rematches == 0 {
+            continue;
+        }
+        let mut remaining = rest.to_vec();
+        remaining.remove(index);
+        if let Some(mut pairs) = pair_teams(&remaining, points, played, rematches - is_rematch as usize, steps) {
+            pairs.insert(0, (team, opponent));
+            return Some(pairs);
+        }
+    }
+    None
+}
+
+/// The next round of a Swiss stage. Teams are paired with others on the same
+/// record where possible, and never with a team they've already played
+/// unless every pairing needs a rematch, in which case the one with the
+/// fewest rematches is used. With an odd number of teams, the lowest ranked
+/// team that hasn't had a bye yet sits the round out.
+pub fn swiss_round(stage: &Stage) -> Vec<Matchup> {
+    let table = standings(stage);
+    let mut order: Vec<&Id> = table.iter().map(|standing| &standing.team).collect();
+    let points: HashMap<&Id, usize> = table.iter()
+        .map(|standing| (&standing.team, standing.points()))
+        .collect();
+
+    let mut bye = None;
+    if order.len() % 2 == 1 {
+        let index = table.iter()
+            .rposition(|standing| standing.byes == 0)
+            .unwrap_or(order.len() - 1);
+        bye = Some(order.remove(index));
+    }
+
+    let played: HashSet<(&Id, &Id)> = stage.matchups()
+        .filter_map(|matchup| Some((matchup.team1.as_ref()?, matchup.team2.as_ref()?)))
+        .flat_map(|(team1, team2)| [(team1, team2), (team2, team1)])
+        .collect();
+    // Allowing every pair to be a rematch always works on the first try
+    let pairs = (0..=order.len() / 2)
+        .find_map(|rematches| {
+            let mut steps = MAX_PAIRING_STEPS;
+            pair_teams(&order, &points, &played, rematches, &mut steps)
+        })
+        .unwrap_or_default();
+
+    pairs.into_iter()
+        .map(|(team1, team2)| Matchup::new(Some(team1.clone()), Some(team2.clone()), 0, 0, false))
+        .chain(bye.map(|team| Matchup::new(Some(team.clone()), None, 0, 0, true)))
+        .collect()
+}
+
+impl Stage {
+    /// Replaces the stage's matchups with a fresh schedule for its teams,
+    /// throwing away any results. Swiss stages only get their first round,
+    /// since the rest depend on how it goes.
+    pub fn generate_schedule(&mut self) {
+        self.rounds = Vec::new();
+        match self.format {
+            StageFormat::RoundRobin => self.rounds = round_robin_schedule(&self.teams),
+            StageFormat::Swiss { round_count } if round_count > 0 => self.rounds.push(swiss_round(self)),
+            StageFormat::Swiss { .. } => {},
+        }
+    }
+
+    /// Adds the next round of a Swiss stage once the current one is finished.
+    pub fn generate_next_round(&mut self) -> CommandResult<()> {
+        let StageFormat::Swiss { round_count } = self.format else {
+            return Err(CommandError::validation(format!("{} is not a Swiss stage", self.name)));
+        };
+        if self.rounds.len() >= round_count {
+            return Err(CommandError::validation(format!("All {} rounds of {} have been played", round_count, self.name)));
+        }
+        if self.matchups().any(|matchup| !matchup.completed) {
+            return Err(CommandError::validation(format!("The current round of {} isn't finished yet", self.name)));
+        }
+        self.rounds.push(swiss_round(self));
+        Ok(())
+    }
+}
+
+impl Division {
+    pub fn stage(&self, id: &str) -> Option<&Stage> {
+        self.stages.iter().find(|stage| stage.id == id)
+    }
+
+    pub fn stage_mut(&mut self, id: &str) -> Option<&mut Stage> {
+        self.stages.iter_mut().find(|stage| stage.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Round;
+
+    fn team_ids(count: usize) -> Vec<Id> {
+        (0..count).map(|i| format!("team{}", i)).collect()
+    }
+
+    fn swiss_stage(teams: &[Id]) -> Stage {
+        Stage::new("Swiss", StageFormat::Swiss { round_count: 5 }, teams.to_vec())
+    }
+
+    fn result(team1: &Id, team2: &Id, team1_score: usize, team2_score: usize) -> Matchup {
+        Matchup::new(Some(team1.clone()), Some(team2.clone()), team1_score, team2_score, true)
+    }
+
+    fn played_teams(round: &[Matchup]) -> Vec<&Id> {
+        let mut teams: Vec<&Id> = round.iter()
+            .flat_map(|matchup| matchup.team1.iter().chain(&matchup.team2))
+            .collect();
+        teams.sort();
+        teams
+    }
+
+    #[test]
+    fn round_robin_plays_everyone_once() {
+        let teams = team_ids(5);
+        let rounds = round_robin_schedule(&teams);
+        assert_eq!(rounds.len(), 5);
+        let mut pairs = HashSet::new();
+        for round in &rounds {
+            assert_eq!(round.len(), 2);
+            for matchup in round {
+                let mut pair = [matchup.team1.clone().unwrap(), matchup.team2.clone().unwrap()];
+                pair.sort();
+                assert!(pairs.insert(pair));
+            }
+        }
+        assert_eq!(pairs.len(), 10);
+    }
+
+    #[test]
+    fn swiss_bye_goes_to_lowest_team_without_one() {
+        let teams = team_ids(5);
+        let mut stage = swiss_stage(&teams);
+        stage.rounds.push(vec![
+            result(&teams[0], &teams[1], 2, 0),
+            result(&teams[2], &teams[3], 2, 0),
+            Matchup::new(Some(teams[4].clone()), None, 0, 0, true),
+        ]);
+
+        let round = swiss_round(&stage);
+        assert_eq!(round.len(), 3);
+        let bye = round.iter().find(|matchup| !matchup.is_filled()).unwrap();
+        // team4 already sat out, and team1 and team3 are level at the bottom
+        assert_eq!(bye.team1.as_ref(), Some(&teams[3]));
+        assert!(bye.completed);
+        assert_eq!(played_teams(&round), teams.iter().collect::<Vec<_>>());
+        let table = standings(&stage);
+        let team4 = table.iter().find(|standing| standing.team == teams[4]).unwrap();
+        assert_eq!((team4.wins, team4.byes, team4.played), (1, 1, 1));
+    }
+
+    #[test]
+    fn swiss_avoids_rematches() {
+        let teams = team_ids(4);
+        let mut stage = swiss_stage(&teams);
+        stage.rounds.push(vec![
+            result(&teams[0], &teams[1], 2, 0),
+            result(&teams[2], &teams[3], 2, 0),
+        ]);
+        stage.rounds.push(swiss_round(&stage));
+        let round = &stage.rounds[1];
+        // The winners play each other, and so do the losers
+        assert!(round.iter().any(|matchup| matchup.is_filled()
+            && [&teams[0], &teams[2]].contains(&matchup.team1.as_ref().unwrap())
+            && [&teams[0], &teams[2]].contains(&matchup.team2.as_ref().unwrap())));
+
+        for matchup in stage.matchups_mut() {
+            matchup.team1_score = 2;
+            matchup.completed = true;
+        }
+        let third = swiss_round(&stage);
+        let played: HashSet<_> = stage.matchups()
+            .map(|matchup| (matchup.team1.clone(), matchup.team2.clone()))
+            .collect();
+        for matchup in &third {
+            assert!(!played.contains(&(matchup.team1.clone(), matchup.team2.clone())));
+            assert!(!played.contains(&(matchup.team2.clone(), matchup.team1.clone())));
+        }
+    }
+
+    #[test]
+    fn swiss_pairing_gives_up_on_impossible_searches() {
+        // The first 11 teams have played the last 9, so each group is left
+        // with an odd number of teams and at least one rematch is needed
+        let teams = team_ids(20);
+        let mut stage = swiss_stage(&teams);
+        stage.rounds = teams[..11].iter()
+            .map(|team| teams[11..].iter().map(|other| result(team, other, 1, 1)).collect())
+            .collect();
+
+        let round = swiss_round(&stage);
+        assert_eq!(round.len(), 10);
+        let mut expected: Vec<&Id> = teams.iter().collect();
+        expected.sort();
+        assert_eq!(played_teams(&round), expected);
+    }
+
+    #[test]
+    fn swiss_standings_break_ties_by_buchholz() {
+        let teams = team_ids(6);
+        let mut stage = swiss_stage(&teams);
+        stage.rounds.push(vec![
+            result(&teams[0], &teams[1], 2, 0),
+            result(&teams[2], &teams[3], 2, 0),
+            result(&teams[4], &teams[5], 2, 0),
+        ]);
+        stage.rounds.push(vec![
+            result(&teams[0], &teams[2], 2, 0),
+            result(&teams[1], &teams[4], 2, 0),
+            result(&teams[3], &teams[5], 2, 0),
+        ]);
+
+        let table = standings(&stage);
+        let rank_of = |team: &Id| table.iter().find(|standing| &standing.team == team).unwrap().rank;
+        let buchholz_of = |team: &Id| table.iter().find(|standing| &standing.team == team).unwrap().buchholz;
+        // Four teams are 1-1, but team1 and team2 lost to the unbeaten team0
+        // while team3 and team4 only beat the winless team5
+        assert_eq!(rank_of(&teams[0]), 1);
+        assert_eq!((buchholz_of(&teams[1]), buchholz_of(&teams[2])), (3, 3));
+        assert_eq!((buchholz_of(&teams[3]), buchholz_of(&teams[4])), (1, 1));
+        assert_eq!((rank_of(&teams[1]), rank_of(&teams[2])), (2, 2));
+        assert_eq!((rank_of(&teams[3]), rank_of(&teams[4])), (4, 4));
+        assert_eq!(rank_of(&teams[5]), 6);
+    }
+
+    #[test]
+    fn standings_count_round_differential() {
+        let teams = team_ids(2);
+        let mut stage = Stage::new("Group", StageFormat::RoundRobin, teams.clone());
+        let mut matchup = result(&teams[0], &teams[1], 1, 0);
+        matchup.rounds.push(Round {
+            team1_score: 3,
+            team2_score: 1,
+            completed: true,
+            ..Default::default()
+        });
+        stage.rounds.push(vec![matchup]);
+
+        let table = standings(&stage);
+        assert_eq!(table[0].team, teams[0]);
+        assert_eq!((table[0].round_differential, table[1].round_differential), (2, -2));
+        assert_eq!((table[0].rank, table[1].rank), (1, 2));
+    }
+}
@@ -0,0 +1,28 @@
+use std::{collections::HashMap, error::Error, path::Path};
+use tera::Tera;
+
+pub fn tera_is_null(args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+    match args.get("value") {
+        Some(value) => Ok(serde_json::Value::Bool(value.is_null())),
+        None => Err("No value provided".into()),
+    }
+}
+
+pub fn load_templates(templates_dir: &Path) -> tera::Result<Tera> {
+    let glob = format!("{}/**/*", templates_dir.to_string_lossy());
+    let mut tera = Tera::new(&glob)?;
+    tera.register_function("is_null", tera_is_null);
+    Ok(tera)
+}
+
+/// Tera buries the useful part of its errors (file, line, what went wrong)
+/// in the source chain, so flatten the whole thing into one message.
+pub fn describe_tera_error(err: &tera::Error) -> String {
+    let mut description = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        description.push_str(&format!(": {}", err));
+        source = err.source();
+    }
+    description.trim().to_string()
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, watch};
+
+// Enough for overlays to lag behind a burst of edits without being dropped
+const UPDATE_CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSection {
+    Settings,
+    Division,
+    Resources,
+    CurrentMatch,
+    Templates,
+    All,
+}
+
+/// Where a change came from, so the desktop UI knows which ones it hasn't seen.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateOrigin {
+    Desktop,
+    Remote,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct StateUpdate {
+    pub section: DataSection,
+    pub revision: u64,
+    pub origin: UpdateOrigin,
+}
+
+impl StateUpdate {
+    pub fn new(section: DataSection, revision: u64, origin: UpdateOrigin) -> Self {
+        Self {
+            section,
+            revision,
+            origin,
+        }
+    }
+}
+
+/// Whether the data has changed since it was last loaded or saved.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct DirtyState {
+    pub dirty: bool,
+    /// Counts every change to the data, unlike `StateUpdate::revision`
+    /// which also counts template reloads
+    pub revision: u64,
+    /// The revision that was last loaded or saved, or none if the file on
+    /// disk has changed since then
+    pub saved_revision: Option<u64>,
+}
+
+impl DirtyState {
+    pub fn new(revision: u64, saved_revision: Option<u64>) -> Self {
+        Self {
+            dirty: saved_revision != Some(revision),
+            revision,
+            saved_revision,
+        }
+    }
+}
+
+pub fn create_update_channel() -> broadcast::Sender<StateUpdate> {
+    let (tx, _) = broadcast::channel(UPDATE_CHANNEL_CAPACITY);
+    tx
+}
+
+pub fn create_dirty_channel() -> watch::Sender<DirtyState> {
+    let (tx, _) = watch::channel(DirtyState::new(0, Some(0)));
+    tx
+}
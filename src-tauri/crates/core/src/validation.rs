@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::Path};
+
+use crate::{
+    migrations,
+    models::{Id, ResourcePair, SaveData, Settings},
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    Image,
+    String,
+}
+
+/// Something in a project that templates (or the app) can't make sense of.
+/// `location` describes where it is in terms the operator will recognize.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// Points at something that doesn't exist (anymore)
+    DanglingReference { location: String, id: Id },
+    /// A round's map that isn't one of its gamemode's maps
+    MapNotInGamemode { location: String, map: Id, gamemode: Option<Id> },
+    DuplicateResourceName { resource: ResourceKind, name: String },
+    /// Resource names have to work as template variables
+    InvalidResourceName { resource: ResourceKind, name: String },
+    DuplicateId { id: Id },
+    /// A template lookup that still treats a reference as a list index
+    IndexedTemplateLookup { template: String, line: usize, lookup: String },
+}
+
+impl Problem {
+    /// Whether `repair` knows how to fix this without guessing.
+    pub fn is_repairable(&self) -> bool {
+        matches!(self, Problem::DanglingReference { .. } | Problem::MapNotInGamemode { .. })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct RepairReport {
+    pub repaired: Vec<Problem>,
+    pub remaining: Vec<Problem>,
+}
+
+pub fn validate(data: &SaveData) -> Vec<Problem> {
+    check(&mut data.clone(), false)
+}
+
+/// Clears every broken reference, reporting what was fixed and what still
+/// needs the operator's attention.
+pub fn repair(data: &mut SaveData) -> RepairReport {
+    let (repaired, remaining) = check(data, true).into_iter()
+        .partition(Problem::is_repairable);
+    RepairReport {
+        repaired,
+        remaining,
+    }
+}
+
+/// Looks through every template in `templates_dir` (and its subfolders) for
+/// lookups written before references were IDs, which fail to render now.
+pub fn check_templates(templates_dir: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut dirs = vec![templates_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let Ok(contents) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let template = path.strip_prefix(templates_dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            for (line, lookup) in migrations::find_index_lookups(&contents) {
+                problems.push(Problem::IndexedTemplateLookup { template: template.clone(), line, lookup });
+            }
+        }
+    }
+    problems
+}
+
+pub fn is_valid_resource_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn check_reference(
+    reference: &mut Option<Id>,
+    exists: impl Fn(&str) -> bool,
+    location: String,
+    repair: bool,
+    problems: &mut Vec<Problem>,
+) {
+    let Some(id) = reference.as_deref() else {
+        return;
+    };
+    if !exists(id) {
+        problems.push(Problem::DanglingReference { location, id: id.to_string() });
+        if repair {
+            *reference = None;
+        }
+    }
+}
+
+fn check_round_map(
+    gamemode: &Option<Id>,
+    map: &mut Option<Id>,
+    settings: &Settings,
+    location: String,
+    repair: bool,
+    problems: &mut Vec<Problem>,
+) {
+    let Some(map_id) = map.as_deref() else {
+        return;
+    };
+    let in_gamemode = gamemode.as_deref()
+        .and_then(|id| settings.gamemode(id))
+        .is_some_and(|gamemode| gamemode.map(map_id).is_some());
+    if in_gamemode {
+        return;
+    }
+
+    let exists = settings.gamemodes.iter().any(|gamemode| gamemode.map(map_id).is_some());
+    problems.push(if exists {
+        Problem::MapNotInGamemode {
+            location,
+            map: map_id.to_string(),
+            gamemode: gamemode.clone(),
+        }
+    } else {
+        Problem::DanglingReference { location, id: map_id.to_string() }
+    });
+    if repair {
+        *map = None;
+    }
+}
+
+fn check_resources(resource: ResourceKind, pairs: &[ResourcePair], problems: &mut Vec<Problem>) {
+    let mut seen = HashSet::new();
+    let mut reported = HashSet::new();
+    for pair in pairs {
+        if !is_valid_resource_name(&pair.name) {
+            problems.push(Problem::InvalidResourceName { resource, name: pair.name.clone() });
+        }
+        if !seen.insert(&pair.name) && reported.insert(&pair.name) {
+            problems.push(Problem::DuplicateResourceName { resource, name: pair.name.clone() });
+        }
+    }
+}
+
+fn check_ids(data: &SaveData, problems: &mut Vec<Problem>) {
+    let settings = &data.settings;
+    let ids = settings.gamemodes.iter().map(|gamemode| &gamemode.id)
+        .chain(settings.gamemodes.iter().flat_map(|gamemode| &gamemode.maps).map(|map| &map.id))
+        .chain(settings.roles.iter().map(|role| &role.id))
+        .chain(settings.characters.iter().map(|character| &character.id))
+        .chain(data.division.teams.iter().map(|team| &team.id))
+        .chain(data.division.teams.iter().flat_map(|team| &team.players).map(|player| &player.id))
+        .chain(data.division.bracket_matchups().map(|matchup| &matchup.id))
+        .chain(data.division.stages.iter().map(|stage| &stage.id))
+        .chain(data.division.stages.iter().flat_map(|stage| stage.matchups()).map(|matchup| &matchup.id));
+
+    let mut seen = HashSet::new();
+    let mut reported = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) && reported.insert(id) {
+            problems.push(Problem::DuplicateId { id: id.clone() });
+        }
+    }
+}
+
+fn check(data: &mut SaveData, repair: bool) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    check_ids(data, &mut problems);
+
+    let SaveData { settings, division, resources, current_match, .. } = data;
+    let team_ids: HashSet<Id> = division.teams.iter().map(|team| team.id.clone()).collect();
+    let team_exists = |id: &str| team_ids.contains(id);
+
+    check_reference(&mut current_match.team1, team_exists,
+        String::from("current match, team 1"), repair, &mut problems);
+    check_reference(&mut current_match.team2, team_exists,
+        String::from("current match, team 2"), repair, &mut problems);
+
+    let matchup_ids: HashSet<Id> = division.bracket_matchups()
+        .chain(division.stages.iter().flat_map(|stage| stage.matchups()))
+        .map(|matchup| matchup.id.clone())
+        .collect();
+    check_reference(&mut current_match.matchup, |id| matchup_ids.contains(id),
+        String::from("current match, linked matchup"), repair, &mut problems);
+
+    for (i, round) in current_match.rounds.iter_mut().enumerate() {
+        check_reference(&mut round.gamemode, |id| settings.gamemode(id).is_some(),
+            format!("current match, round {} gamemode", i + 1), repair, &mut problems);
+        check_round_map(&round.gamemode, &mut round.map, settings,
+            format!("current match, round {} map", i + 1), repair, &mut problems);
+    }
+
+    for (position, matchup) in division.bracket_matchups_mut() {
+        check_reference(&mut matchup.team1, team_exists,
+            format!("{}, team 1", position), repair, &mut problems);
+        check_reference(&mut matchup.team2, team_exists,
+            format!("{}, team 2", position), repair, &mut problems);
+    }
+
+    for stage in division.stages.iter_mut() {
+        let mut dangling = Vec::new();
+        for id in &stage.teams {
+            if !team_exists(id) {
+                dangling.push(id.clone());
+                problems.push(Problem::DanglingReference {
+                    location: format!("stage \"{}\" teams", stage.name),
+                    id: id.clone(),
+                });
+            }
+        }
+        if repair {
+            stage.teams.retain(|id| !dangling.contains(id));
+        }
+
+        for (i, round) in stage.rounds.iter_mut().enumerate() {
+            for (j, matchup) in round.iter_mut().enumerate() {
+                let location = format!("stage \"{}\" round {}, matchup {}", stage.name, i + 1, j + 1);
+                check_reference(&mut matchup.team1, team_exists,
+                    format!("{}, team 1", location), repair, &mut problems);
+                check_reference(&mut matchup.team2, team_exists,
+                    format!("{}, team 2", location), repair, &mut problems);
+            }
+        }
+    }
+
+    for team in division.teams.iter_mut() {
+        for player in team.players.iter_mut() {
+            check_reference(&mut player.role, |id| settings.role(id).is_some(),
+                format!("team \"{}\", player \"{}\" role", team.name, player.name), repair, &mut problems);
+            check_reference(&mut player.character, |id| settings.character(id).is_some(),
+                format!("team \"{}\", player \"{}\" character", team.name, player.name), repair, &mut problems);
+        }
+    }
+
+    check_resources(ResourceKind::Image, &resources.images, &mut problems);
+    check_resources(ResourceKind::String, &resources.strings, &mut problems);
+
+    problems
+}
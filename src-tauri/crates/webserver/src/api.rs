@@ -0,0 +1,53 @@
+use axum::{
+    extract::Extension,
+    http::header,
+    response::{IntoResponse, Json},
+};
+use std::sync::Arc;
+
+use scoreboard_core::{context::{self, ContextMap}, models::SaveData};
+
+use crate::WebserverState;
+
+// Custom overlays are often served from somewhere else (e.g. a dev server),
+// and everything here is read-only, so let anyone fetch it
+fn json_response(
+    webserver_state: &WebserverState,
+    build: impl FnOnce(&SaveData) -> ContextMap,
+) -> impl IntoResponse {
+    let state = webserver_state.shared_state.lock().unwrap();
+    (
+        [(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")],
+        Json(build(&state.data)),
+    )
+}
+
+pub async fn get_state(
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> impl IntoResponse {
+    json_response(&webserver_state, context::full_context)
+}
+
+pub async fn get_match(
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> impl IntoResponse {
+    json_response(&webserver_state, context::match_context)
+}
+
+pub async fn get_division(
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> impl IntoResponse {
+    json_response(&webserver_state, context::division_context)
+}
+
+pub async fn get_settings(
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> impl IntoResponse {
+    json_response(&webserver_state, context::settings_context)
+}
+
+pub async fn get_resources(
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> impl IntoResponse {
+    json_response(&webserver_state, context::resources_context)
+}
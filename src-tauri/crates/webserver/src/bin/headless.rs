@@ -0,0 +1,145 @@
+// Serves a project's overlays and HTTP API without opening the desktop app,
+// e.g. on a machine without a display or from CI.
+
+use std::{path::PathBuf, process::ExitCode};
+use tokio::{net::TcpListener, sync::oneshot};
+
+use scoreboard_core::{
+    ProjectState,
+    autosave,
+    error::{CommandError, CommandResult},
+    fs,
+    models::SaveData,
+    templates,
+};
+
+const USAGE: &str = "Usage: live-scoreboard-headless <config file> [--host <host>] [--port <port>]";
+
+struct Args {
+    config_path: PathBuf,
+    /// Overrides the webserver host from the config without changing it
+    host: Option<String>,
+    /// Overrides the webserver port from the config without changing it
+    port: Option<u16>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut config_path = None;
+    let mut host = None;
+    let mut port = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--host" => {
+                host = Some(args.next().ok_or("Missing value for --host")?);
+            },
+            "--port" => {
+                let value = args.next().ok_or("Missing value for --port")?;
+                port = Some(value.parse().map_err(|_| format!("Invalid port {:?}", value))?);
+            },
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {:?}", arg)),
+            _ if config_path.is_none() => config_path = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument {:?}", arg)),
+        }
+    }
+
+    let config_path = config_path.ok_or("Missing config file")?;
+    Ok(Args { config_path, host, port })
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            if !err.is_empty() {
+                eprintln!("{}", err);
+            }
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+async fn run(args: Args) -> CommandResult<()> {
+    let shared_state = ProjectState::new_shared(None, SaveData::default());
+
+    let (templates_path, addr) = {
+        let mut state = shared_state.lock().unwrap();
+        fs::read_into_state_from_config_file(&mut state, &args.config_path)?;
+        state.mark_saved();
+
+        let mut webserver_settings = state.data.settings.webserver.clone();
+        if let Some(host) = args.host {
+            webserver_settings.host = host;
+        }
+        if let Some(port) = args.port {
+            webserver_settings.port = port;
+        }
+        let addr = webserver_settings.socket_addr()
+            .map_err(CommandError::validation)?;
+
+        if state.data.settings.api_token.is_none() {
+            println!("No API token is set, so changes can't be made over HTTP");
+        }
+        let base_path = state.get_base_path().ok_or(CommandError::NoConfigLoaded)?;
+        (base_path.join("templates"), addr)
+    };
+
+    templates::load_templates(&templates_path)
+        .map_err(|err| CommandError::Template { message: templates::describe_tera_error(&err) })?;
+
+    let bind_error = |err: std::io::Error| CommandError::WebserverBind {
+        address: addr.to_string(),
+        message: err.to_string(),
+    };
+    let listener = TcpListener::bind(addr).await
+        .map_err(bind_error)?;
+    println!("Webserver listening on {}", listener.local_addr().map_err(bind_error)?);
+
+    // Changes made over HTTP are saved the same way the desktop app saves its own
+    let autosave_task = tokio::spawn(autosave::run_autosave(shared_state.clone()));
+
+    let (tx, rx) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => {
+                println!("Stopping webserver");
+                let _ = tx.send(());
+            },
+            // Dropping the sender would stop the server, so keep it around
+            Err(err) => {
+                eprintln!("Error listening for Ctrl+C: {:?}", err);
+                std::future::pending::<()>().await;
+                drop(tx);
+            },
+        }
+    });
+
+    let result = scoreboard_webserver::create_and_run_webserver(
+        templates_path,
+        listener,
+        rx,
+        shared_state.clone(),
+    ).await;
+    autosave_task.abort();
+
+    // Don't lose whatever came in since the last autosave
+    let mut state = shared_state.lock().unwrap();
+    if state.is_dirty() && state.data.settings.autosave_interval.is_some() {
+        let path = args.config_path;
+        fs::autosave_config_file(&mut state, &path)?;
+        state.mark_saved();
+    }
+
+    result.map_err(|message| CommandError::Webserver { message })
+}
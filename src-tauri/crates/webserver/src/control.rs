@@ -0,0 +1,299 @@
+use axum::{
+    async_trait,
+    extract::{Extension, FromRequestParts, Path, Query},
+    http::{header, request::Parts},
+    response::Json,
+};
+use serde::{Deserialize, Deserializer};
+use std::{collections::HashMap, sync::Arc};
+
+use scoreboard_core::{
+    context::{self, ContextMap},
+    models::{Division, Id, Match, Resources, Round, SaveData, Settings},
+    updates::{DataSection, UpdateOrigin},
+};
+
+use crate::{error::AppError, WebserverState};
+
+/// Extracting this checks the request's API token against the one in the
+/// project settings, taken from either an `Authorization: Bearer` header or
+/// a `token` query parameter (for tools that can't set headers).
+pub struct Authorized;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Authorized {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Extension(webserver_state) =
+            Extension::<Arc<WebserverState>>::from_request_parts(parts, state).await
+                .map_err(|_| AppError::WriteApiDisabled)?;
+        let expected = webserver_state.shared_state.lock().unwrap()
+            .data.settings.api_token.clone()
+            .filter(|token| !token.is_empty())
+            .ok_or(AppError::WriteApiDisabled)?;
+
+        let from_header = parts.headers.get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+        let from_query = Query::<HashMap<String, String>>::from_request_parts(parts, state).await
+            .ok()
+            .and_then(|Query(mut query)| query.remove("token"));
+
+        match from_header.or(from_query) {
+            Some(token) if tokens_match(&token, &expected) => Ok(Authorized),
+            _ => Err(AppError::Unauthorized),
+        }
+    }
+}
+
+// Looks at every byte however early they differ, so how long the check takes
+// doesn't give away how much of a guessed token was right
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// Lets PATCH bodies tell "leave it alone" (missing) apart from "clear it" (null)
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MatchPatch {
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub team1: Option<Option<Id>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub team2: Option<Option<Id>>,
+    pub swap_scoreboard: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub matchup: Option<Option<Id>>,
+}
+
+/// Settings sent with a PUT, which only change the API token if it's in the
+/// body, so clients that don't know about it can't lock everyone else out.
+#[derive(Debug, Deserialize)]
+pub struct SettingsBody {
+    #[serde(flatten)]
+    pub settings: Settings,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub api_token: Option<Option<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoundPatch {
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub gamemode: Option<Option<Id>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub map: Option<Option<Id>>,
+    pub team1_score: Option<usize>,
+    pub team2_score: Option<usize>,
+    pub completed: Option<bool>,
+}
+
+fn update_data<T>(
+    webserver_state: &WebserverState,
+    section: DataSection,
+    update: impl FnOnce(&mut SaveData) -> Result<T, AppError>,
+    build: impl FnOnce(&SaveData) -> ContextMap,
+) -> Result<Json<ContextMap>, AppError> {
+    let mut state = webserver_state.shared_state.lock().unwrap();
+    state.update_section(section, UpdateOrigin::Remote, update)?;
+    Ok(Json(build(&state.data)))
+}
+
+/// Also keeps the matchup the match is linked to up to date.
+fn update_current_match(
+    webserver_state: &WebserverState,
+    update: impl FnOnce(&mut SaveData) -> Result<(), AppError>,
+) -> Result<Json<ContextMap>, AppError> {
+    let mut state = webserver_state.shared_state.lock().unwrap();
+    state.update_current_match(UpdateOrigin::Remote, update).0?;
+    Ok(Json(context::match_context(&state.data)))
+}
+
+fn update_match(
+    webserver_state: &WebserverState,
+    update: impl FnOnce(&mut Match) -> Result<(), AppError>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_current_match(webserver_state, |data| update(&mut data.current_match))
+}
+
+// Rounds are numbered from 1 in URLs, the same as in the app
+fn get_round(current_match: &mut Match, round: usize) -> Result<&mut Round, AppError> {
+    round.checked_sub(1)
+        .and_then(|index| current_match.rounds.get_mut(index))
+        .ok_or(AppError::NotFound)
+}
+
+fn get_team_score(round: &mut Round, team: usize) -> Result<&mut usize, AppError> {
+    match team {
+        1 => Ok(&mut round.team1_score),
+        2 => Ok(&mut round.team2_score),
+        _ => Err(AppError::BadRequest(format!("Team must be 1 or 2, not {}", team))),
+    }
+}
+
+pub async fn put_match(
+    _: Authorized,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+    Json(current_match): Json<Match>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_current_match(&webserver_state, |data| {
+        if let Some(id) = &current_match.matchup {
+            if data.division.matchup(id).is_none() {
+                return Err(AppError::BadRequest(format!("No matchup with ID {}", id)));
+            }
+        }
+        data.current_match = current_match;
+        Ok(())
+    })
+}
+
+pub async fn patch_match(
+    _: Authorized,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+    Json(patch): Json<MatchPatch>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_current_match(&webserver_state, |data| {
+        // Linking first sets up the teams, which the rest can then change
+        if let Some(matchup) = patch.matchup {
+            data.link_matchup(matchup)
+                .map_err(|err| AppError::BadRequest(err.to_string()))?;
+        }
+        let current_match = &mut data.current_match;
+        if let Some(team1) = patch.team1 {
+            current_match.team1 = team1;
+        }
+        if let Some(team2) = patch.team2 {
+            current_match.team2 = team2;
+        }
+        if let Some(swap_scoreboard) = patch.swap_scoreboard {
+            current_match.swap_scoreboard = swap_scoreboard;
+        }
+        Ok(())
+    })
+}
+
+pub async fn load_next_matchup(
+    _: Authorized,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_data(&webserver_state, DataSection::CurrentMatch, |data| {
+        data.load_next_matchup()
+            .map_err(|err| AppError::BadRequest(err.to_string()))
+    }, context::match_context)
+}
+
+pub async fn toggle_swap_scoreboard(
+    _: Authorized,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_match(&webserver_state, |current_match| {
+        current_match.swap_scoreboard = !current_match.swap_scoreboard;
+        Ok(())
+    })
+}
+
+pub async fn patch_round(
+    _: Authorized,
+    Path(round): Path<usize>,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+    Json(patch): Json<RoundPatch>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_match(&webserver_state, |current_match| {
+        let round = get_round(current_match, round)?;
+        if let Some(gamemode) = patch.gamemode {
+            round.gamemode = gamemode;
+        }
+        if let Some(map) = patch.map {
+            round.map = map;
+        }
+        if let Some(team1_score) = patch.team1_score {
+            round.team1_score = team1_score;
+        }
+        if let Some(team2_score) = patch.team2_score {
+            round.team2_score = team2_score;
+        }
+        if let Some(completed) = patch.completed {
+            round.completed = completed;
+        }
+        Ok(())
+    })
+}
+
+pub async fn increment_score(
+    _: Authorized,
+    Path((round, team)): Path<(usize, usize)>,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_match(&webserver_state, |current_match| {
+        let score = get_team_score(get_round(current_match, round)?, team)?;
+        *score += 1;
+        Ok(())
+    })
+}
+
+pub async fn decrement_score(
+    _: Authorized,
+    Path((round, team)): Path<(usize, usize)>,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_match(&webserver_state, |current_match| {
+        let score = get_team_score(get_round(current_match, round)?, team)?;
+        *score = score.saturating_sub(1);
+        Ok(())
+    })
+}
+
+pub async fn toggle_round_completed(
+    _: Authorized,
+    Path(round): Path<usize>,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_match(&webserver_state, |current_match| {
+        let round = get_round(current_match, round)?;
+        round.completed = !round.completed;
+        Ok(())
+    })
+}
+
+pub async fn put_division(
+    _: Authorized,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+    Json(division): Json<Division>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_data(&webserver_state, DataSection::Division, |data| {
+        let before = std::mem::replace(&mut data.division, division);
+        data.advance_bracket_from(&before);
+        Ok(())
+    }, context::division_context)
+}
+
+pub async fn put_settings(
+    _: Authorized,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+    Json(SettingsBody { mut settings, api_token }): Json<SettingsBody>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_data(&webserver_state, DataSection::All, |data| {
+        settings.api_token = api_token.unwrap_or_else(|| data.settings.api_token.clone());
+        data.replace_settings(settings)
+            .map_err(|err| AppError::BadRequest(err.to_string()))
+    }, context::settings_context)
+}
+
+pub async fn put_resources(
+    _: Authorized,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+    Json(resources): Json<Resources>,
+) -> Result<Json<ContextMap>, AppError> {
+    update_data(&webserver_state, DataSection::Resources, |data| {
+        data.resources = resources;
+        Ok(())
+    }, context::resources_context)
+}
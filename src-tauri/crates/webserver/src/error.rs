@@ -8,6 +8,9 @@ use std::io;
 pub enum AppError {
     TemplateError,
     NotFound,
+    Unauthorized,
+    WriteApiDisabled,
+    BadRequest(String),
 }
 
 impl IntoResponse for AppError {
@@ -21,6 +24,18 @@ impl IntoResponse for AppError {
                 StatusCode::NOT_FOUND,
                 String::from("Not found"),
             ).into_response(),
+            AppError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                String::from("Missing or incorrect API token"),
+            ).into_response(),
+            AppError::WriteApiDisabled => (
+                StatusCode::FORBIDDEN,
+                String::from("Write API is disabled, set an API token to enable it"),
+            ).into_response(),
+            AppError::BadRequest(reason) => (
+                StatusCode::BAD_REQUEST,
+                reason,
+            ).into_response(),
         }
     }
 }
@@ -0,0 +1,111 @@
+pub mod api;
+pub mod control;
+pub mod error;
+pub mod routes;
+
+use std::{path::PathBuf, sync::{Arc, RwLock}, time::Duration};
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode, DebounceEventResult};
+use serde::{Deserialize, Serialize};
+use tera::Tera;
+use tokio::{net::TcpListener, sync::{oneshot, watch}};
+
+use scoreboard_core::{
+    SharedState,
+    templates::{describe_tera_error, load_templates},
+    updates::DataSection,
+};
+
+// Editors tend to write files in several steps, so wait for things to settle
+const TEMPLATE_RELOAD_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum WebserverStatus {
+    Stopped,
+    Starting,
+    Running { address: String },
+    Failed { reason: String },
+}
+
+impl WebserverStatus {
+    pub fn is_active(&self) -> bool {
+        matches!(self, WebserverStatus::Starting | WebserverStatus::Running { .. })
+    }
+}
+
+// Shared application state
+pub struct WebserverState {
+    pub tera: RwLock<Tera>,
+    pub templates_dir: PathBuf,
+    pub shared_state: SharedState,
+    pub closing_rx: watch::Receiver<bool>,
+}
+
+impl WebserverState {
+    pub fn new(
+        templates_dir: PathBuf,
+        shared_state: SharedState,
+        closing_rx: watch::Receiver<bool>,
+    ) -> tera::Result<Self> {
+        let tera = load_templates(&templates_dir)?;
+        shared_state.lock().unwrap().set_template_error(None);
+        Ok(Self {
+            tera: RwLock::new(tera),
+            templates_dir,
+            shared_state,
+            closing_rx,
+        })
+    }
+
+    /// Reparses every template, keeping the current set if any of them fail.
+    pub fn reload_templates(&self) {
+        match load_templates(&self.templates_dir) {
+            Ok(tera) => {
+                *self.tera.write().unwrap() = tera;
+                let mut state = self.shared_state.lock().unwrap();
+                state.set_template_error(None);
+                state.mark_updated(DataSection::Templates);
+                println!("Templates reloaded successfully");
+            },
+            Err(err) => {
+                let description = describe_tera_error(&err);
+                eprintln!("Failed to reload templates: {}", description);
+                self.shared_state.lock().unwrap().set_template_error(Some(description));
+            },
+        }
+    }
+}
+
+pub async fn create_and_run_webserver(
+    templates_dir: PathBuf,
+    listener: TcpListener,
+    shutdown_rx: oneshot::Receiver<()>,
+    shared_state: SharedState,
+) -> Result<(), String> {
+    let (closing_tx, closing_rx) = watch::channel(false);
+    let webserver_state = WebserverState::new(templates_dir.clone(), shared_state, closing_rx)
+        .map_err(|err| format!("Failed to load templates: {}", describe_tera_error(&err)))?;
+    let webserver_state = Arc::new(webserver_state);
+
+    // Kept alive until the server stops, at which point dropping it stops the watch
+    let watcher_state = webserver_state.clone();
+    let mut debouncer = new_debouncer(TEMPLATE_RELOAD_DELAY, move |result: DebounceEventResult| {
+        match result {
+            Ok(_) => watcher_state.reload_templates(),
+            Err(err) => eprintln!("Error watching templates: {:?}", err),
+        }
+    }).map_err(|err| format!("Failed to create template watcher: {}", err))?;
+    if let Err(err) = debouncer.watcher().watch(&templates_dir, RecursiveMode::Recursive) {
+        eprintln!("Failed to watch templates, changes will need a restart: {:?}", err);
+    }
+
+    let app = routes::create_router(webserver_state);
+    
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            shutdown_rx.await.ok();
+            // Event streams never end on their own, so close them to let the server stop
+            closing_tx.send(true).ok();
+        }).await
+        .map_err(|err| format!("Webserver stopped unexpectedly: {}", err))
+}
@@ -0,0 +1,47 @@
+// Keeps an overlay in sync with the app. Include it with:
+//
+//     <script src="/live.js" data-sections="current_match settings"></script>
+//
+// `data-sections` is optional and limits which changes trigger an update.
+// Template changes always trigger one.
+// By default the page reloads itself, but it can patch itself instead by
+// listening for `scoreboard-update` on the document and calling
+// `preventDefault()` on the event.
+(() => {
+    const script = document.currentScript;
+    const sections = script && script.dataset.sections
+        ? script.dataset.sections.split(/\s+/)
+        : null;
+    let revision = null;
+
+    function dispatch(update) {
+        if (sections !== null && !["all", "templates"].includes(update.section)
+            && !sections.includes(update.section)) {
+            return;
+        }
+        const event = new CustomEvent("scoreboard-update", {
+            detail: update,
+            cancelable: true,
+        });
+        if (document.dispatchEvent(event)) {
+            window.location.reload();
+        }
+    }
+
+    const source = new EventSource("/events");
+
+    source.addEventListener("connected", (e) => {
+        const update = JSON.parse(e.data);
+        // Anything could have changed while we were disconnected
+        if (revision !== null && update.revision !== revision) {
+            dispatch(update);
+        }
+        revision = update.revision;
+    });
+
+    source.addEventListener("update", (e) => {
+        const update = JSON.parse(e.data);
+        revision = update.revision;
+        dispatch(update);
+    });
+})();
@@ -0,0 +1,201 @@
+use axum::{
+    body::Body,
+    extract::{Extension, Path, Query},
+    response::{sse::{Event, KeepAlive, Sse}, Html, IntoResponse, Response},
+    routing::{get, patch, post, MethodRouter},
+    Router,
+};
+use tera::{Context, Tera};
+use tokio_stream::{wrappers::{BroadcastStream, WatchStream}, Stream, StreamExt};
+use std::{collections::HashMap, fs, sync::Arc};
+use std::error::Error;
+use scoreboard_core::{
+    context::populate_context,
+    fs::from_relative_path,
+    models::SaveData,
+    updates::{DataSection, StateUpdate, UpdateOrigin},
+};
+
+use crate::{api, control, error::AppError, WebserverState};
+
+const LIVE_SCRIPT: &str = include_str!("live.js");
+
+pub fn create_router(webserver_state: Arc<WebserverState>) -> Router {
+    Router::new()
+        .route("/overlay/*name", get(render_overlay))
+        .route("/bracket", overlay_alias("bracket"))
+        .route("/assets/*path", get(serve_asset))
+        .route("/team/:team", get(render_team))
+        .route("/scoreboard", overlay_alias("scoreboard"))
+        .route("/rounds", overlay_alias("rounds"))
+        .route("/waiting", overlay_alias("waiting"))
+        .route("/casters", overlay_alias("casters"))
+        .route("/standings", overlay_alias("standings"))
+        .route("/events", get(stream_updates))
+        .route("/live.js", get(serve_live_script))
+        .route("/api/state", get(api::get_state))
+        .route("/api/match", get(api::get_match).put(control::put_match).patch(control::patch_match))
+        .route("/api/match/next", post(control::load_next_matchup))
+        .route("/api/match/swap_scoreboard/toggle", post(control::toggle_swap_scoreboard))
+        .route("/api/match/rounds/:round", patch(control::patch_round))
+        .route("/api/match/rounds/:round/completed/toggle", post(control::toggle_round_completed))
+        .route("/api/match/rounds/:round/team/:team/increment", post(control::increment_score))
+        .route("/api/match/rounds/:round/team/:team/decrement", post(control::decrement_score))
+        .route("/api/division", get(api::get_division).put(control::put_division))
+        .route("/api/settings", get(api::get_settings).put(control::put_settings))
+        .route("/api/resources", get(api::get_resources).put(control::put_resources))
+        .layer(Extension(webserver_state))
+}
+
+/// Finds the template for an overlay name, with or without the `.html`.
+fn find_template(tera: &Tera, name: &str) -> Option<String> {
+    let name = name.trim_start_matches('/');
+    let with_extension = format!("{}.html", name);
+    tera.get_template_names()
+        .find(|template| *template == name || *template == with_extension)
+        .map(|template| template.to_string())
+}
+
+fn render_template(
+    webserver_state: &WebserverState,
+    name: &str,
+    query: HashMap<String, String>,
+    extend_context: impl FnOnce(&mut Context, &SaveData) -> Result<(), AppError>,
+) -> Result<Html<String>, AppError> {
+    let mut context = Context::new();
+    let state = webserver_state.shared_state.lock().unwrap();
+    
+    populate_context(&mut context, &state.data);
+    context.insert("query", &query);
+    extend_context(&mut context, &state.data)?;
+
+    let tera = webserver_state.tera.read().unwrap();
+    let template = find_template(&tera, name).ok_or(AppError::NotFound)?;
+
+    match tera.render(&template, &context) {
+        Ok(rendered) => Ok(Html(rendered)),
+        Err(e) => {
+            eprintln!("Failed to render template: {:?}", e.source());
+            Err(AppError::TemplateError)
+        }
+    }
+}
+
+/// Renders any template in the project, so new graphics don't need their own route.
+pub async fn render_overlay(
+    Path(name): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> Result<Html<String>, AppError> {
+    render_template(&webserver_state, &name, query, |_, _| Ok(()))
+}
+
+/// Shorthand route for one of the standard graphics, e.g. `/scoreboard`.
+fn overlay_alias(name: &'static str) -> MethodRouter {
+    get(move |query: Query<HashMap<String, String>>, extension: Extension<Arc<WebserverState>>| {
+        render_overlay(Path(name.to_string()), query, extension)
+    })
+}
+
+pub async fn render_team(
+    Path(team_number): Path<usize>,
+    Query(query): Query<HashMap<String, String>>,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> Result<Html<String>, AppError> {
+    render_template(&webserver_state, "team", query, |context, data| {
+        let team_id = if team_number == 1 {
+            &data.current_match.team1
+        } else {
+            &data.current_match.team2
+        };
+
+        let team = team_id.as_deref()
+            .and_then(|id| data.division.team(id))
+            .ok_or(AppError::NotFound)?;
+
+        context.insert("team", team);
+        Ok(())
+    })
+}
+
+pub async fn serve_asset(
+    Path(path): Path<String>,
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> Result<Response, AppError> {
+    if path.contains("..") {
+        return Err(AppError::NotFound);
+    }
+
+    let state = webserver_state.shared_state.lock().unwrap();
+
+    let Some(base_path) = state.get_base_path() else {
+        eprintln!("Failed to get base path");
+        return Err(AppError::NotFound);
+    };
+
+    let path = path.trim_start_matches("/");
+    let path = format!("assets/{}", path);
+    let path = from_relative_path(&base_path, &path);
+
+    // Match path end with whether it is text or binary
+    let content_type = get_content_type(path.as_str());
+    let body = if content_type.starts_with("text") {
+        Body::from(fs::read_to_string(path)?)
+    } else {
+        Body::from(fs::read(path)?)
+    };
+
+    let mut response = Response::new(body);
+    response.headers_mut().insert("Content-Type", content_type.parse().unwrap());
+    Ok(response)
+}
+
+pub async fn stream_updates(
+    Extension(webserver_state): Extension<Arc<WebserverState>>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let shared_state = webserver_state.shared_state.clone();
+    let (revision, rx) = {
+        let state = shared_state.lock().unwrap();
+        (state.revision, state.update_tx.subscribe())
+    };
+
+    // The first event tells the page which revision it is looking at
+    let connected = Event::default()
+        .event("connected")
+        .json_data(StateUpdate::new(DataSection::All, revision, UpdateOrigin::Desktop));
+
+    let updates = BroadcastStream::new(rx).map(move |update| {
+        // If the overlay fell too far behind, just tell it everything changed
+        let update = update.unwrap_or_else(|_| {
+            let revision = shared_state.lock().unwrap().revision;
+            StateUpdate::new(DataSection::All, revision, UpdateOrigin::Remote)
+        });
+        Some(Event::default().event("update").json_data(update))
+    });
+    let closing = WatchStream::from_changes(webserver_state.closing_rx.clone())
+        .map(|_| None);
+
+    let events = tokio_stream::once(Some(connected))
+        .chain(updates.merge(closing))
+        .map_while(|event| event);
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+}
+
+pub async fn serve_live_script() -> impl IntoResponse {
+    ([("Content-Type", "text/javascript")], LIVE_SCRIPT)
+}
+
+fn get_content_type(path: &str) -> &'static str {
+    match path.split('.').last() {
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("png") => "image/png",
+        Some("jpg") => "image/jpeg",
+        Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
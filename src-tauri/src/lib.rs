@@ -1,194 +1,451 @@
-pub mod webserver;
-pub mod models;
-pub mod fs;
-
 use std::sync::{Arc, Mutex};
-use tokio::{self, sync::oneshot::{self, Sender}};
-use tauri::{State, Builder, Manager, async_runtime::JoinHandle};
+use tokio::{self, sync::{broadcast, oneshot::{self, Sender}}};
+use tauri::{AppHandle, State, Builder, Emitter, Manager, async_runtime::JoinHandle};
 
-use models::{SaveData, Settings, Division, Resources, Match};
+use scoreboard_core::{
+    ProjectState,
+    SharedState,
+    autosave,
+    backups,
+    bracket::ResizePreview,
+    config_watcher::{self, ExternalChange, ExternalChangeAction, MergeReport},
+    error::{CommandError, CommandResult},
+    fs,
+    history::{ChangeSummary, HistorySummary},
+    models::{SaveData, Settings, Division, Resources, Match, MatchResult},
+    stages::{self, Standing},
+    templates,
+    updates::{DataSection, DirtyState, StateUpdate, UpdateOrigin},
+    validation,
+};
+use scoreboard_webserver::WebserverStatus;
 
+/// The webserver task, managed separately from the project it serves.
 #[derive(Debug)]
-pub struct AppState {
-    pub loaded_config: Option<std::path::PathBuf>,
-    pub webserver_handle: Option<JoinHandle<()>>,
-    pub webserver_stop_tx: Option<Sender<()>>,
-    pub data: SaveData,
-}
-
-type SharedState = Arc<Mutex<AppState>>;
-
-impl AppState {
-    pub fn new(
-        loaded_config: Option<std::path::PathBuf>,
-        webserver_handle: Option<JoinHandle<()>>,
-        webserver_stop_tx: Option<Sender<()>>,
-        data: SaveData,
-    ) -> Self {
+pub struct WebserverHandle {
+    pub task: Option<JoinHandle<()>>,
+    pub stop_tx: Option<Sender<()>>,
+    pub status: WebserverStatus,
+}
+
+impl Default for WebserverHandle {
+    fn default() -> Self {
         Self {
-            loaded_config,
-            webserver_handle,
-            webserver_stop_tx,
-            data,
+            task: None,
+            stop_tx: None,
+            status: WebserverStatus::Stopped,
         }
     }
+}
 
-    pub fn new_shared(
-        loaded_config: Option<std::path::PathBuf>,
-        webserver_handle: Option<JoinHandle<()>>,
-        webserver_stop_tx: Option<Sender<()>>,
-        data: SaveData,
-    ) -> SharedState {
-        Arc::new(Mutex::new(AppState::new(
-            loaded_config,
-            webserver_handle,
-            webserver_stop_tx,
-            data,
-        )))
-    }
+type SharedWebserver = Arc<Mutex<WebserverHandle>>;
 
-    pub fn get_base_path(&self) -> Option<std::path::PathBuf> {
-        self.loaded_config.as_ref()
-            .map(|path| fs::remove_file_from_path(path))
+fn set_webserver_status(app: &AppHandle, webserver: &mut WebserverHandle, status: WebserverStatus) {
+    webserver.status = status.clone();
+    if let Err(err) = app.emit("webserver-status", status) {
+        eprintln!("Failed to notify UI of webserver status: {:?}", err);
     }
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self::new(
-            None,
-            None,
-            None,
-            SaveData::default(),
-        )
-    }
-}
+/// Binds to the address in the settings and returns the one actually bound,
+/// which differs from the settings when asking for port 0.
+async fn launch_webserver(
+    app: &AppHandle,
+    shared_state: &SharedState,
+    shared_webserver: &SharedWebserver,
+) -> CommandResult<String> {
+    let (base_path, addr) = {
+        let state = shared_state.lock().unwrap();
+        let base_path = state.get_base_path().ok_or(CommandError::NoConfigLoaded)?;
+        let addr = state.data.settings.webserver.socket_addr()
+            .map_err(CommandError::validation)?;
+        (base_path, addr)
+    };
 
-#[tauri::command]
-fn start_webserver(shared_state: State<'_, SharedState>) -> bool {
-    let Some(base_path) = shared_state.lock().unwrap().get_base_path() else {
-        println!("Failed to start webserver: no config loaded!");
-        return false;
+    // Catch broken templates before reporting that everything is running
+    let templates_path = base_path.join("templates");
+    templates::load_templates(&templates_path)
+        .map_err(|err| CommandError::Template { message: templates::describe_tera_error(&err) })?;
+
+    let bind_error = |err: std::io::Error| CommandError::WebserverBind {
+        address: addr.to_string(),
+        message: err.to_string(),
     };
-    let templates_path = fs::from_relative_path(&base_path, "templates/**/*");
+    let listener = tokio::net::TcpListener::bind(addr).await
+        .map_err(bind_error)?;
+    let bound_addr = listener.local_addr()
+        .map_err(bind_error)?
+        .to_string();
+
     let (tx, rx) = oneshot::channel::<()>();
-    let handle = tauri::async_runtime::spawn(
-        webserver::create_and_run_webserver(
+    // Hold the lock until everything is recorded, so a server that fails
+    // immediately can't have its status overwritten with `Running`
+    let mut webserver = shared_webserver.lock().unwrap();
+    let task_app = app.clone();
+    let task_state = shared_state.clone();
+    let task_webserver = shared_webserver.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        let result = scoreboard_webserver::create_and_run_webserver(
             templates_path,
-            "0.0.0.0:3000",
+            listener,
             rx,
-            shared_state.inner().clone()
-        )
-    );
+            task_state,
+        ).await;
+        let mut webserver = task_webserver.lock().unwrap();
+        webserver.task = None;
+        webserver.stop_tx = None;
+        let status = match result {
+            Ok(()) => WebserverStatus::Stopped,
+            Err(reason) => {
+                eprintln!("{}", reason);
+                WebserverStatus::Failed { reason }
+            },
+        };
+        set_webserver_status(&task_app, &mut webserver, status);
+    });
+    webserver.task = Some(task);
+    webserver.stop_tx = Some(tx);
+    set_webserver_status(app, &mut webserver, WebserverStatus::Running { address: bound_addr.clone() });
+    println!("Webserver listening on {}", bound_addr);
+    Ok(bound_addr)
+}
+
+/// Starts watching the loaded config for outside edits, unless it already is.
+fn watch_loaded_config(shared_state: &SharedState, state: &mut ProjectState) {
+    let Some(path) = state.loaded_config.clone() else {
+        return;
+    };
+    if state.config_watcher.as_ref().is_some_and(|watcher| watcher.path == path) {
+        return;
+    }
+    state.config_watcher = config_watcher::watch_config(shared_state.clone(), &path)
+        .inspect_err(|err| eprintln!("Failed to watch config file, outside edits won't be noticed: {:?}", err))
+        .ok();
+}
+
+#[tauri::command]
+async fn start_webserver(
+    app: AppHandle,
+    shared_state: State<'_, SharedState>,
+    shared_webserver: State<'_, SharedWebserver>,
+) -> CommandResult<String> {
     {
-        let mut state = shared_state.lock().unwrap();
-        state.webserver_handle = Some(handle);
-        state.webserver_stop_tx = Some(tx);
+        let mut webserver = shared_webserver.lock().unwrap();
+        if webserver.status.is_active() {
+            return Err(CommandError::WebserverAlreadyRunning);
+        }
+        set_webserver_status(&app, &mut webserver, WebserverStatus::Starting);
     }
-    true
+    launch_webserver(&app, shared_state.inner(), shared_webserver.inner()).await.inspect_err(|err| {
+        let mut webserver = shared_webserver.lock().unwrap();
+        set_webserver_status(&app, &mut webserver, WebserverStatus::Failed { reason: err.to_string() });
+    })
 }
 
+/// Waits for the webserver to actually stop, so it can be started again right away.
 #[tauri::command]
-fn stop_webserver(shared_state: State<'_, SharedState>) -> bool {
-    let mut state = shared_state.lock().unwrap();
-    if let Some(tx) = state.webserver_stop_tx.take() {
-        return tx.send(()).is_ok()
+async fn stop_webserver(shared_webserver: State<'_, SharedWebserver>) -> CommandResult<bool> {
+    let (tx, task) = {
+        let mut webserver = shared_webserver.lock().unwrap();
+        (webserver.stop_tx.take(), webserver.task.take())
+    };
+    let Some(tx) = tx else {
+        return Ok(false);
+    };
+    tx.send(()).ok();
+    if let Some(task) = task {
+        task.await.map_err(|err| CommandError::Webserver {
+            message: format!("did not stop cleanly: {}", err),
+        })?;
     }
-    true
+    Ok(true)
+}
+
+#[tauri::command]
+fn get_webserver_status(shared_webserver: State<'_, SharedWebserver>) -> CommandResult<WebserverStatus> {
+    let webserver = shared_webserver.lock().unwrap();
+    Ok(webserver.status.clone())
 }
 
 #[tauri::command]
-fn get_loaded_config(shared_state: State<'_, SharedState>) -> Option<String> {
+fn get_template_error(shared_state: State<'_, SharedState>) -> CommandResult<Option<String>> {
     let state = shared_state.lock().unwrap();
-    state.loaded_config.as_ref()
-        .map(|path| path.to_string_lossy().to_string())
+    Ok(state.template_error.clone())
 }
 
 #[tauri::command]
-fn get_settings(shared_state: State<'_, SharedState>) -> Settings {
+fn get_loaded_config(shared_state: State<'_, SharedState>) -> CommandResult<Option<String>> {
     let state = shared_state.lock().unwrap();
-    state.data.settings.clone()
+    Ok(state.loaded_config.as_ref()
+        .map(|path| path.to_string_lossy().to_string()))
 }
 
 #[tauri::command]
-fn set_settings(shared_state: State<'_, SharedState>, settings: Settings) {
+fn get_settings(shared_state: State<'_, SharedState>) -> CommandResult<Settings> {
+    let state = shared_state.lock().unwrap();
+    Ok(state.data.settings.clone())
+}
+
+#[tauri::command]
+fn set_settings(shared_state: State<'_, SharedState>, settings: Settings) -> CommandResult<()> {
     let mut state = shared_state.lock().unwrap();
-    state.data.settings = settings;
+    state.update_section(DataSection::All, UpdateOrigin::Desktop, |data| data.replace_settings(settings))
 }
 
 #[tauri::command]
-fn get_division(shared_state: State<'_, SharedState>) -> Division {
+fn get_division(shared_state: State<'_, SharedState>) -> CommandResult<Division> {
     let state = shared_state.lock().unwrap();
-    state.data.division.clone()
+    Ok(state.data.division.clone())
 }
 
+/// Returns the division if moving teams through the bracket changed it, so
+/// the UI can show where they ended up.
 #[tauri::command]
-fn set_division(shared_state: State<'_, SharedState>, division: Division) {
+fn set_division(shared_state: State<'_, SharedState>, division: Division) -> CommandResult<Option<Division>> {
     let mut state = shared_state.lock().unwrap();
-    state.data.division = division;
+    let advanced = state.update_section(DataSection::Division, UpdateOrigin::Desktop, |data| {
+        let before = std::mem::replace(&mut data.division, division.clone());
+        data.advance_bracket_from(&before);
+        (data.division != division).then(|| data.division.clone())
+    });
+    Ok(advanced)
 }
 
 #[tauri::command]
-fn get_resources(shared_state: State<'_, SharedState>) -> Resources {
+fn get_resources(shared_state: State<'_, SharedState>) -> CommandResult<Resources> {
     let state = shared_state.lock().unwrap();
-    state.data.resources.clone()
+    Ok(state.data.resources.clone())
 }
 
 #[tauri::command]
-fn set_resources(shared_state: State<'_, SharedState>, resources: Resources) {
+fn set_resources(shared_state: State<'_, SharedState>, resources: Resources) -> CommandResult<()> {
     let mut state = shared_state.lock().unwrap();
-    state.data.resources = resources;
+    state.update_section(DataSection::Resources, UpdateOrigin::Desktop, |data| data.resources = resources);
+    Ok(())
 }
 
 #[tauri::command]
-fn get_current_match(shared_state: State<'_, SharedState>) -> Match {
+fn get_current_match(shared_state: State<'_, SharedState>) -> CommandResult<Match> {
     let state = shared_state.lock().unwrap();
-    state.data.current_match.clone()
+    Ok(state.data.current_match.clone())
+}
+
+/// Returns whether the matchup the match is linked to changed, in which case
+/// the UI needs the division again.
+#[tauri::command]
+fn set_current_match(shared_state: State<'_, SharedState>, current_match: Match) -> CommandResult<bool> {
+    let mut state = shared_state.lock().unwrap();
+    let ((), synced) = state.update_current_match(UpdateOrigin::Desktop, |data| data.current_match = current_match);
+    Ok(synced)
+}
+
+/// Returns the current match, set up from the matchup it's now linked to.
+#[tauri::command]
+fn link_matchup(shared_state: State<'_, SharedState>, matchup: Option<String>) -> CommandResult<Match> {
+    let mut state = shared_state.lock().unwrap();
+    state.update_section(DataSection::CurrentMatch, UpdateOrigin::Desktop, |data| data.link_matchup(matchup))?;
+    Ok(state.data.current_match.clone())
+}
+
+#[tauri::command]
+fn load_next_matchup(shared_state: State<'_, SharedState>) -> CommandResult<()> {
+    let mut state = shared_state.lock().unwrap();
+    state.update_section(DataSection::CurrentMatch, UpdateOrigin::Desktop, |data| data.load_next_matchup())?;
+    Ok(())
 }
 
+/// Returns what was undone, or nothing if there was nothing to undo.
 #[tauri::command]
-fn set_current_match(shared_state: State<'_, SharedState>, current_match: Match) {
+fn undo(shared_state: State<'_, SharedState>) -> CommandResult<Option<ChangeSummary>> {
     let mut state = shared_state.lock().unwrap();
-    state.data.current_match = current_match;
+    Ok(state.undo())
 }
 
 #[tauri::command]
-fn load_from_filename(shared_state: State<'_, SharedState>, filename: String) -> bool {
+fn redo(shared_state: State<'_, SharedState>) -> CommandResult<Option<ChangeSummary>> {
+    let mut state = shared_state.lock().unwrap();
+    Ok(state.redo())
+}
+
+#[tauri::command]
+fn get_history(shared_state: State<'_, SharedState>) -> CommandResult<HistorySummary> {
+    let state = shared_state.lock().unwrap();
+    Ok(state.history.summary())
+}
+
+#[tauri::command]
+fn get_match_result(shared_state: State<'_, SharedState>) -> CommandResult<MatchResult> {
+    let state = shared_state.lock().unwrap();
+    Ok(state.data.current_match.result(&state.data.settings.match_format))
+}
+
+#[tauri::command]
+fn validate_project(shared_state: State<'_, SharedState>) -> CommandResult<Vec<validation::Problem>> {
+    let state = shared_state.lock().unwrap();
+    let mut problems = validation::validate(&state.data);
+    if let Some(base_path) = state.get_base_path() {
+        problems.extend(validation::check_templates(&base_path.join("templates")));
+    }
+    Ok(problems)
+}
+
+#[tauri::command]
+fn repair_project(shared_state: State<'_, SharedState>) -> CommandResult<validation::RepairReport> {
+    let mut state = shared_state.lock().unwrap();
+    let report = state.update_section(DataSection::All, UpdateOrigin::Desktop, validation::repair);
+    Ok(report)
+}
+
+#[tauri::command]
+fn load_from_filename(shared_state: State<'_, SharedState>, filename: String) -> CommandResult<()> {
     let path = std::path::Path::new(&filename);
     let mut state = shared_state.lock().unwrap();
-    fs::read_into_state_from_config_file(&mut state, path);
-    true
+    fs::read_into_state_from_config_file(&mut state, path)
+        .inspect_err(|err| eprintln!("Error opening config file: {}", err))?;
+    state.history.clear();
+    state.mark_updated(DataSection::All);
+    state.mark_saved();
+    watch_loaded_config(shared_state.inner(), &mut state);
+    Ok(())
 }
 
 #[tauri::command]
-fn save_to_filename(shared_state: State<'_, SharedState>, filename: String) -> bool {
+fn save_to_filename(shared_state: State<'_, SharedState>, filename: String) -> CommandResult<()> {
     let path = std::path::Path::new(&filename);
     let mut state = shared_state.lock().unwrap();
-    fs::write_state_to_config_file(&mut state, path);
-    true
+    fs::write_state_to_config_file(&mut state, path)
+        .inspect_err(|err| eprintln!("Error saving config file: {}", err))?;
+    state.mark_saved();
+    watch_loaded_config(shared_state.inner(), &mut state);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_external_change(shared_state: State<'_, SharedState>) -> CommandResult<Option<ExternalChange>> {
+    let state = shared_state.lock().unwrap();
+    Ok(state.external_change.clone())
+}
+
+#[tauri::command]
+fn resolve_external_change(
+    shared_state: State<'_, SharedState>,
+    action: ExternalChangeAction,
+) -> CommandResult<MergeReport> {
+    let mut state = shared_state.lock().unwrap();
+    config_watcher::resolve_external_change(&mut state, action)
+}
+
+#[tauri::command]
+fn get_dirty_state(shared_state: State<'_, SharedState>) -> CommandResult<DirtyState> {
+    let state = shared_state.lock().unwrap();
+    Ok(state.dirty_state())
 }
 
 #[tauri::command]
-fn to_relative_path(shared_state: State<'_, SharedState>, path: String) -> Option<String> {
-    let base_path = shared_state.lock().unwrap().get_base_path()?;
-    fs::to_relative_path(&base_path, &std::path::Path::new(&path))
+fn list_backups(shared_state: State<'_, SharedState>) -> CommandResult<Vec<backups::Backup>> {
+    let state = shared_state.lock().unwrap();
+    let path = state.loaded_config.as_ref().ok_or(CommandError::NoConfigLoaded)?;
+    backups::list_backups(path)
 }
 
+/// Overwrites the loaded config with one of its backups and loads it.
 #[tauri::command]
-fn from_relative_path(shared_state: State<'_, SharedState>, path: String) -> Option<String> {
-    shared_state.lock().unwrap().get_base_path().map(|base_path| {
+fn restore_backup(shared_state: State<'_, SharedState>, name: String) -> CommandResult<()> {
+    let mut state = shared_state.lock().unwrap();
+    let path = state.loaded_config.clone().ok_or(CommandError::NoConfigLoaded)?;
+    backups::restore_backup(&path, &name)?;
+    fs::read_into_state_from_config_file(&mut state, &path)?;
+    state.history.clear();
+    state.mark_updated(DataSection::All);
+    state.mark_saved();
+    Ok(())
+}
+
+#[tauri::command]
+fn to_relative_path(shared_state: State<'_, SharedState>, path: String) -> CommandResult<Option<String>> {
+    let Some(base_path) = shared_state.lock().unwrap().get_base_path() else {
+        return Ok(None);
+    };
+    Ok(fs::to_relative_path(&base_path, &std::path::Path::new(&path)))
+}
+
+#[tauri::command]
+fn from_relative_path(shared_state: State<'_, SharedState>, path: String) -> CommandResult<Option<String>> {
+    Ok(shared_state.lock().unwrap().get_base_path().map(|base_path| {
         fs::from_relative_path(&base_path, &path)
+    }))
+}
+
+#[tauri::command]
+fn correct_rounds_to_count(shared_state: State<'_, SharedState>) -> CommandResult<()> {
+    let mut state = shared_state.lock().unwrap();
+    state.update_section(DataSection::CurrentMatch, UpdateOrigin::Desktop, |data| data.correct_rounds_to_count());
+    Ok(())
+}
+
+#[tauri::command]
+fn correct_bracket_to_count(shared_state: State<'_, SharedState>) -> CommandResult<()> {
+    let mut state = shared_state.lock().unwrap();
+    state.update_section(DataSection::Division, UpdateOrigin::Desktop, |data| data.correct_bracket_to_count());
+    Ok(())
+}
+
+fn no_stage(stage_id: &str) -> CommandError {
+    CommandError::validation(format!("No stage with ID {}", stage_id))
+}
+
+#[tauri::command]
+fn generate_stage_schedule(shared_state: State<'_, SharedState>, stage_id: String) -> CommandResult<()> {
+    let mut state = shared_state.lock().unwrap();
+    state.update_section(DataSection::Division, UpdateOrigin::Desktop, |data| {
+        let stage = data.division.stage_mut(&stage_id).ok_or_else(|| no_stage(&stage_id))?;
+        stage.generate_schedule();
+        Ok(())
     })
 }
 
 #[tauri::command]
-fn correct_rounds_to_count(shared_state: State<'_, SharedState>) {
-    shared_state.lock().unwrap().data.correct_rounds_to_count();
+fn generate_next_round(shared_state: State<'_, SharedState>, stage_id: String) -> CommandResult<()> {
+    let mut state = shared_state.lock().unwrap();
+    state.update_section(DataSection::Division, UpdateOrigin::Desktop, |data| {
+        data.division.stage_mut(&stage_id)
+            .ok_or_else(|| no_stage(&stage_id))?
+            .generate_next_round()
+    })
+}
+
+#[tauri::command]
+fn get_standings(shared_state: State<'_, SharedState>, stage_id: String) -> CommandResult<Vec<Standing>> {
+    let state = shared_state.lock().unwrap();
+    let stage = state.data.division.stage(&stage_id).ok_or_else(|| no_stage(&stage_id))?;
+    Ok(stages::standings(stage))
 }
 
 #[tauri::command]
-fn correct_bracket_to_count(shared_state: State<'_, SharedState>) {
-    shared_state.lock().unwrap().data.correct_bracket_to_count();
+fn preview_bracket_resize(shared_state: State<'_, SharedState>, stage_count: usize) -> CommandResult<ResizePreview> {
+    let state = shared_state.lock().unwrap();
+    state.data.preview_bracket_resize(stage_count)
+}
+
+/// Sets the stage count as well as resizing the bracket, so both sections change.
+#[tauri::command]
+fn resize_bracket(shared_state: State<'_, SharedState>, stage_count: usize) -> CommandResult<()> {
+    let mut state = shared_state.lock().unwrap();
+    state.update_section(DataSection::All, UpdateOrigin::Desktop, |data| data.resize_bracket(stage_count))
+}
+
+#[tauri::command]
+fn recompute_bracket(shared_state: State<'_, SharedState>) -> CommandResult<()> {
+    let mut state = shared_state.lock().unwrap();
+    state.update_section(DataSection::Division, UpdateOrigin::Desktop, |data| data.advance_bracket());
+    Ok(())
+}
+
+/// Sets the bracket's size as well as its teams, so both sections change.
+#[tauri::command]
+fn seed_bracket(shared_state: State<'_, SharedState>, seeds: Vec<String>) -> CommandResult<()> {
+    let mut state = shared_state.lock().unwrap();
+    state.update_section(DataSection::All, UpdateOrigin::Desktop, |data| data.seed_bracket(&seeds))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -197,13 +454,75 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
-            app.manage(Arc::new(Mutex::new(AppState::default())));
+            let shared_state = ProjectState::new_shared(None, SaveData::default());
+            let mut update_rx = shared_state.lock().unwrap().update_tx.subscribe();
+            let mut dirty_rx = shared_state.lock().unwrap().dirty_tx.subscribe();
+            let mut external_change_rx = shared_state.lock().unwrap().external_change_tx.subscribe();
+            let mut template_error_rx = shared_state.lock().unwrap().template_error_tx.subscribe();
+            let listener_state = shared_state.clone();
+            tauri::async_runtime::spawn(autosave::run_autosave(shared_state.clone()));
+            app.manage(shared_state);
+            app.manage(SharedWebserver::default());
+
+            // Keep the UI's unsaved changes indicator up to date
+            let dirty_app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while dirty_rx.changed().await.is_ok() {
+                    let dirty_state = *dirty_rx.borrow_and_update();
+                    if let Err(err) = dirty_app_handle.emit("dirty-state", dirty_state) {
+                        eprintln!("Failed to notify UI of dirty state: {:?}", err);
+                    }
+                }
+            });
+
+            // Ask the UI what to do when the config is edited elsewhere
+            let external_change_app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while external_change_rx.changed().await.is_ok() {
+                    let change = external_change_rx.borrow_and_update().clone();
+                    if let Err(err) = external_change_app_handle.emit("external-change", change) {
+                        eprintln!("Failed to notify UI of external change: {:?}", err);
+                    }
+                }
+            });
+
+            // Show template parse errors as soon as a reload hits one
+            let template_error_app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while template_error_rx.changed().await.is_ok() {
+                    let error = template_error_rx.borrow_and_update().clone();
+                    if let Err(err) = template_error_app_handle.emit("template-error", error) {
+                        eprintln!("Failed to notify UI of template error: {:?}", err);
+                    }
+                }
+            });
+
+            // Let the UI know about changes it didn't make itself (e.g. from the write API)
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let update = match update_rx.recv().await {
+                        Ok(update) if update.origin == UpdateOrigin::Desktop => continue,
+                        Ok(update) => update,
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            let revision = listener_state.lock().unwrap().revision;
+                            StateUpdate::new(DataSection::All, revision, UpdateOrigin::Remote)
+                        },
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    if let Err(err) = app_handle.emit("state-updated", update) {
+                        eprintln!("Failed to notify UI of state update: {:?}", err);
+                    }
+                }
+            });
             Ok(())
         })
         .plugin(tauri_plugin_shell::init())
         .invoke_handler(tauri::generate_handler![
             start_webserver,
             stop_webserver,
+            get_webserver_status,
+            get_template_error,
             get_loaded_config,
             get_settings,
             set_settings,
@@ -213,12 +532,32 @@ pub fn run() {
             set_resources,
             get_current_match,
             set_current_match,
+            link_matchup,
+            load_next_matchup,
+            undo,
+            redo,
+            get_history,
+            get_match_result,
+            validate_project,
+            repair_project,
             load_from_filename,
             save_to_filename,
+            get_dirty_state,
+            get_external_change,
+            resolve_external_change,
+            list_backups,
+            restore_backup,
             to_relative_path,
             from_relative_path,
             correct_rounds_to_count,
             correct_bracket_to_count,
+            preview_bracket_resize,
+            resize_bracket,
+            recompute_bracket,
+            seed_bracket,
+            generate_stage_schedule,
+            generate_next_round,
+            get_standings,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,12 +1,29 @@
 import { invoke, convertFileSrc } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type * as models from "$lib/models";
 
-export async function startWebserver() {
-    await invoke("start_webserver");
+export async function startWebserver(): Promise<string> {
+    return await invoke("start_webserver");
 }
 
-export async function stopWebserver() {
-    await invoke("stop_webserver");
+export async function stopWebserver(): Promise<boolean> {
+    return await invoke("stop_webserver");
+}
+
+export async function getWebserverStatus(): Promise<models.WebserverStatus> {
+    return await invoke("get_webserver_status");
+}
+
+export async function onWebserverStatus(callback: (status: models.WebserverStatus) => void): Promise<UnlistenFn> {
+    return await listen<models.WebserverStatus>("webserver-status", (event) => callback(event.payload));
+}
+
+export async function getTemplateError(): Promise<string | null> {
+    return await invoke("get_template_error");
+}
+
+export async function onTemplateError(callback: (error: string | null) => void): Promise<UnlistenFn> {
+    return await listen<string | null>("template-error", (event) => callback(event.payload));
 }
 
 export async function getLoadedConfig(): Promise<string | null> {
@@ -25,8 +42,9 @@ export async function getDivision(): Promise<models.Division> {
     return await invoke("get_division");
 }
 
-export async function setDivision(division: models.Division) {
-    await invoke("set_division", { division });
+// returns the division again if teams were moved on through the bracket
+export async function setDivision(division: models.Division): Promise<models.Division | null> {
+    return await invoke("set_division", { division });
 }
 
 export async function getResources(): Promise<models.Resources> {
@@ -41,16 +59,78 @@ export async function getCurrentMatch(): Promise<models.Match> {
     return await invoke("get_current_match");
 }
 
-export async function setCurrentMatch(match: models.Match) {
-    await invoke("set_current_match", { currentMatch: match });
+// returns whether the linked matchup changed along with it
+export async function setCurrentMatch(match: models.Match): Promise<boolean> {
+    return await invoke("set_current_match", { currentMatch: match });
+}
+
+// returns the current match, set up from the newly linked matchup
+export async function linkMatchup(matchup: string | null): Promise<models.Match> {
+    return await invoke("link_matchup", { matchup });
+}
+
+export async function loadNextMatchup(): Promise<void> {
+    await invoke("load_next_matchup");
+}
+
+export async function undo(): Promise<models.ChangeSummary | null> {
+    return await invoke("undo");
+}
+
+export async function redo(): Promise<models.ChangeSummary | null> {
+    return await invoke("redo");
+}
+
+export async function getHistory(): Promise<models.HistorySummary> {
+    return await invoke("get_history");
+}
+
+export async function getMatchResult(): Promise<models.MatchResult> {
+    return await invoke("get_match_result");
+}
+
+export async function validateProject(): Promise<models.Problem[]> {
+    return await invoke("validate_project");
+}
+
+export async function repairProject(): Promise<models.RepairReport> {
+    return await invoke("repair_project");
+}
+
+export async function loadFromFilename(filename: string): Promise<void> {
+    await invoke("load_from_filename", { filename });
+}
+
+export async function saveToFilename(filename: string): Promise<void> {
+    await invoke("save_to_filename", { filename });
+}
+
+export async function getDirtyState(): Promise<models.DirtyState> {
+    return await invoke("get_dirty_state");
+}
+
+export async function onDirtyState(callback: (state: models.DirtyState) => void): Promise<UnlistenFn> {
+    return await listen<models.DirtyState>("dirty-state", (event) => callback(event.payload));
+}
+
+export async function getExternalChange(): Promise<models.ExternalChange | null> {
+    return await invoke("get_external_change");
 }
 
-export async function loadFromFilename(filename: string): Promise<boolean> {
-    return await invoke("load_from_filename", { filename });
+export async function onExternalChange(callback: (change: models.ExternalChange | null) => void): Promise<UnlistenFn> {
+    return await listen<models.ExternalChange | null>("external-change", (event) => callback(event.payload));
 }
 
-export async function saveToFilename(filename: string): Promise<boolean> {
-    return await invoke("save_to_filename", { filename });
+export async function resolveExternalChange(action: models.ExternalChangeAction): Promise<models.MergeReport> {
+    return await invoke("resolve_external_change", { action });
+}
+
+export async function listBackups(): Promise<models.Backup[]> {
+    return await invoke("list_backups");
+}
+
+export async function restoreBackup(name: string): Promise<void> {
+    await invoke("restore_backup", { name });
 }
 
 export async function fromRelativePath(path: string): Promise<string | null> {
@@ -78,3 +158,35 @@ export async function correctRoundsToCount(): Promise<void> {
 export async function correctBracketToCount(): Promise<void> {
     await invoke("correct_bracket_to_count");
 }
+
+export async function generateStageSchedule(stageId: string): Promise<void> {
+    await invoke("generate_stage_schedule", { stageId });
+}
+
+export async function generateNextRound(stageId: string): Promise<void> {
+    await invoke("generate_next_round", { stageId });
+}
+
+export async function getStandings(stageId: string): Promise<models.Standing[]> {
+    return await invoke("get_standings", { stageId });
+}
+
+export async function previewBracketResize(stageCount: number): Promise<models.ResizePreview> {
+    return await invoke("preview_bracket_resize", { stageCount });
+}
+
+export async function resizeBracket(stageCount: number): Promise<void> {
+    await invoke("resize_bracket", { stageCount });
+}
+
+export async function recomputeBracket(): Promise<void> {
+    await invoke("recompute_bracket");
+}
+
+export async function seedBracket(seeds: string[]): Promise<void> {
+    await invoke("seed_bracket", { seeds });
+}
+
+export async function onStateUpdated(callback: (update: models.StateUpdate) => void): Promise<UnlistenFn> {
+    return await listen<models.StateUpdate>("state-updated", (event) => callback(event.payload));
+}
@@ -5,25 +5,48 @@ export interface Settings {
     gamemodes: Gamemode[];
     roles: Role[];
     characters: Character[];
+    api_token: string | null;
+    webserver: WebserverSettings;
+    match_format: MatchFormat;
+    bracket_format: BracketFormat;
+    autosave_interval: number | null;
+}
+
+export type MatchFormat =
+    | { type: "best_of" }
+    | { type: "first_to", wins: number }
+    | { type: "fixed_rounds" };
+
+export type BracketFormat =
+    | { type: "single_elimination" }
+    | { type: "double_elimination", bracket_reset: boolean };
+
+export interface WebserverSettings {
+    host: string;
+    port: number;
 }
 
 export interface Gamemode {
+    id: string;
     name: string;
     icon: string | null;
     maps: Map[];
 }
 
 export interface Map {
+    id: string;
     name: string;
     image: string | null;
 }
 
 export interface Role {
+    id: string;
     name: string;
     icon: string | null;
 }
 
 export interface Character {
+    id: string;
     name: string;
     image: string | null;
 }
@@ -41,39 +64,167 @@ export interface ResourcePair {
 export interface Division {
     teams: Team[];
     bracket: (Matchup | null)[][];
+    lower_bracket: (Matchup | null)[][];
+    grand_final: Matchup | null;
+    grand_final_reset: Matchup | null;
+    stages: Stage[];
 }
 
 export interface Matchup {
-    team1: number | null;
-    team2: number | null;
+    id: string;
+    team1: string | null;
+    team2: string | null;
     team1_score: number;
     team2_score: number;
     completed: boolean;
+    rounds: Round[];
+}
+
+export type StageFormat =
+    | { type: "round_robin" }
+    | { type: "swiss", round_count: number };
+
+export interface Stage {
+    id: string;
+    name: string;
+    format: StageFormat;
+    teams: string[];
+    rounds: Matchup[][];
+}
+
+export interface Standing {
+    rank: number;
+    team: string;
+    played: number;
+    wins: number;
+    draws: number;
+    losses: number;
+    map_wins: number;
+    map_losses: number;
+    map_differential: number;
+    round_wins: number;
+    round_losses: number;
+    round_differential: number;
+    byes: number;
+    buchholz: number;
+    opponent_win_percentage: number;
 }
 
 export interface Match {
     rounds: Round[];
-    team1: number | null;
-    team2: number | null;
+    team1: string | null;
+    team2: string | null;
     swap_scoreboard: boolean;
+    matchup: string | null;
+}
+
+export interface MatchResult {
+    team1_score: number;
+    team2_score: number;
+    decided: boolean;
+    winner: 1 | 2 | null;
+    draw: boolean;
+    moot_rounds: number[];
 }
 
 export interface Round {
-    gamemode: number | null;
-    map: number | null;
+    gamemode: string | null;
+    map: string | null;
     team1_score: number;
     team2_score: number;
     completed: boolean;
 }
 
 export interface Player {
+    id: string;
     name: string;
-    role: number | null;
-    character: number | null;
+    role: string | null;
+    character: string | null;
 }
 
 export interface Team {
+    id: string;
     name: string;
     icon: string | null;
     players: Player[];
 }
+
+export interface BracketPosition {
+    side: "upper" | "lower" | "grand_final" | "grand_final_reset";
+    stage: number;
+    index: number;
+}
+
+export interface ResizePreview {
+    stage_count: number;
+    discarded: BracketPosition[];
+}
+
+export interface StateUpdate {
+    section: "settings" | "division" | "resources" | "current_match" | "templates" | "all";
+    revision: number;
+    origin: "desktop" | "remote";
+}
+
+export interface ChangeSummary {
+    description: string;
+    section: StateUpdate["section"];
+    origin: StateUpdate["origin"];
+}
+
+export interface HistorySummary {
+    undo: ChangeSummary[];
+    redo: ChangeSummary[];
+}
+
+export interface DirtyState {
+    dirty: boolean;
+    revision: number;
+    saved_revision: number | null;
+}
+
+export interface ExternalChange {
+    path: string;
+    conflict: boolean;
+}
+
+export type ExternalChangeAction = "reload" | "merge" | "ignore";
+
+export interface MergeReport {
+    conflicts: string[];
+}
+
+export type WebserverStatus =
+    | { state: "stopped" }
+    | { state: "starting" }
+    | { state: "running", address: string }
+    | { state: "failed", reason: string };
+
+export type Problem =
+    | { kind: "dangling_reference", location: string, id: string }
+    | { kind: "map_not_in_gamemode", location: string, map: string, gamemode: string | null }
+    | { kind: "duplicate_resource_name", resource: "image" | "string", name: string }
+    | { kind: "invalid_resource_name", resource: "image" | "string", name: string }
+    | { kind: "duplicate_id", id: string }
+    | { kind: "indexed_template_lookup", template: string, line: number, lookup: string };
+
+export interface RepairReport {
+    repaired: Problem[];
+    remaining: Problem[];
+}
+
+export interface Backup {
+    name: string;
+    created: string;
+    size: number;
+}
+
+export type CommandError =
+    | { kind: "io", path: string, message: string }
+    | { kind: "parse", path: string, message: string, line: number | null, column: number | null }
+    | { kind: "validation", message: string }
+    | { kind: "webserver_bind", address: string, message: string }
+    | { kind: "webserver", message: string }
+    | { kind: "template", message: string }
+    | { kind: "no_config_loaded" }
+    | { kind: "webserver_already_running" };
@@ -1,4 +1,5 @@
 import { open, save, type DialogFilter } from "@tauri-apps/plugin-dialog";
+import type { BracketPosition, CommandError, Problem } from "$lib/models";
 
 export async function openDialog(filter?: DialogFilter): Promise<string | null> {
     return await open({
@@ -18,24 +19,67 @@ export async function saveDialog(
     });
 }
 
-export function correctIndex(index: number, from: number, to?: number): number | null {
-    if (to === undefined) {
-        if (index === from) {
-            return null;
-        } else if (index > from) {
-            return index - 1;
-        } else {
-            return index;
-        }
-    } else {
-        if (index === from) {
-            return to;
-        } else if (index > from && index <= to) {
-            return index - 1;
-        } else if (index < from && index >= to) {
-            return index + 1;
-        } else {
-            return index;
-        }
+export function findById<T extends { id: string }>(items: T[], id: string | null): T | undefined {
+    return id === null ? undefined : items.find((item) => item.id === id);
+}
+
+// Returns the reference if it still points at one of the items, or null otherwise.
+export function keepIfPresent<T extends { id: string }>(items: T[], id: string | null): string | null {
+    return findById(items, id) === undefined ? null : id;
+}
+
+export function describeProblem(problem: Problem): string {
+    switch (problem.kind) {
+        case "dangling_reference":
+            return `${problem.location} refers to something that no longer exists`;
+        case "map_not_in_gamemode":
+            return `${problem.location} is not one of the round's gamemode's maps`;
+        case "duplicate_resource_name":
+            return `There is more than one ${problem.resource} resource named "${problem.name}"`;
+        case "invalid_resource_name":
+            return `The ${problem.resource} resource "${problem.name}" should only contain letters, numbers, and underscores`;
+        case "duplicate_id":
+            return `More than one item has the ID ${problem.id}`;
+        case "indexed_template_lookup":
+            return `${problem.template} line ${problem.line}: ${problem.lookup} looks up an ID by position, use the matching _by_id map instead`;
+    }
+}
+
+export function describeBracketPosition(position: BracketPosition): string {
+    switch (position.side) {
+        case "upper":
+            return `bracket stage ${position.stage + 1}, matchup ${position.index + 1}`;
+        case "lower":
+            return `lower bracket stage ${position.stage + 1}, matchup ${position.index + 1}`;
+        case "grand_final":
+            return "grand final";
+        case "grand_final_reset":
+            return "grand final reset";
+    }
+}
+
+// Commands reject with a CommandError, but anything else thrown is shown as is.
+export function describeError(error: unknown): string {
+    if (typeof error !== "object" || error === null || !("kind" in error)) {
+        return String(error);
+    }
+    const commandError = error as CommandError;
+    switch (commandError.kind) {
+        case "io":
+            return `Could not access ${commandError.path}: ${commandError.message}`;
+        case "parse":
+            return `Could not read ${commandError.path}: ${commandError.message}`;
+        case "validation":
+            return commandError.message;
+        case "webserver_bind":
+            return `Could not start the webserver on ${commandError.address}: ${commandError.message}`;
+        case "webserver":
+            return `Webserver error: ${commandError.message}`;
+        case "template":
+            return `Failed to load templates: ${commandError.message}`;
+        case "no_config_loaded":
+            return "No config loaded";
+        case "webserver_already_running":
+            return "The webserver is already running";
     }
 }